@@ -1,4 +1,6 @@
 fn main() {
+    // Re-embed SQL migrations when they change
+    println!("cargo:rerun-if-changed=migrations");
 
     tauri_build::build()
-}
\ No newline at end of file
+}
@@ -0,0 +1,7 @@
+-- Richer profiles: bio, pronouns, banner, external links and timezone
+ALTER TABLE profiles
+    ADD COLUMN IF NOT EXISTS bio TEXT,
+    ADD COLUMN IF NOT EXISTS pronouns TEXT,
+    ADD COLUMN IF NOT EXISTS banner_url TEXT,
+    ADD COLUMN IF NOT EXISTS links TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS timezone TEXT;
@@ -0,0 +1,49 @@
+-- Per-user privacy settings. Users without a row get the defaults.
+CREATE TABLE IF NOT EXISTS privacy_settings (
+    user_id TEXT PRIMARY KEY,
+    status_visibility TEXT NOT NULL DEFAULT 'everyone'
+        CHECK (status_visibility IN ('everyone', 'friends', 'nobody')),
+    friend_requests TEXT NOT NULL DEFAULT 'everyone'
+        CHECK (friend_requests IN ('everyone', 'friends_of_friends', 'nobody')),
+    direct_messages TEXT NOT NULL DEFAULT 'everyone'
+        CHECK (direct_messages IN ('everyone', 'friends', 'nobody')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Whether `viewer` may see `owner`'s online status
+CREATE OR REPLACE FUNCTION can_see_status(owner TEXT, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT owner = viewer OR CASE COALESCE(
+        (SELECT status_visibility FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends' THEN EXISTS (
+            SELECT 1 FROM friends WHERE user_id = owner AND friend_id = viewer)
+        ELSE FALSE
+    END
+$$;
+
+-- Whether `requester` may send `owner` a friend request
+CREATE OR REPLACE FUNCTION can_send_friend_request(owner TEXT, requester TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT CASE COALESCE(
+        (SELECT friend_requests FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends_of_friends' THEN EXISTS (
+            SELECT 1 FROM friends a
+            JOIN friends b ON b.user_id = a.friend_id
+            WHERE a.user_id = owner AND b.friend_id = requester)
+        ELSE FALSE
+    END
+$$;
+
+-- Whether `requester` may start a new DM with `owner`
+CREATE OR REPLACE FUNCTION can_start_dm(owner TEXT, requester TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT CASE COALESCE(
+        (SELECT direct_messages FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends' THEN EXISTS (
+            SELECT 1 FROM friends WHERE user_id = owner AND friend_id = requester)
+        ELSE FALSE
+    END
+$$;
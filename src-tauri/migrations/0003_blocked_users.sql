@@ -0,0 +1,53 @@
+-- Users a person has blocked. Blocks apply in both directions for requests, DMs and presence.
+CREATE TABLE IF NOT EXISTS blocked_users (
+    blocker_id TEXT NOT NULL,
+    blocked_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (blocker_id, blocked_id),
+    CHECK (blocker_id <> blocked_id)
+);
+
+CREATE INDEX IF NOT EXISTS blocked_users_blocked_id_idx ON blocked_users (blocked_id);
+
+-- Whether either user has blocked the other
+CREATE OR REPLACE FUNCTION is_blocked_between(a TEXT, b TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM blocked_users
+        WHERE (blocker_id = a AND blocked_id = b) OR (blocker_id = b AND blocked_id = a))
+$$;
+
+CREATE OR REPLACE FUNCTION can_see_status(owner TEXT, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT owner = viewer OR (NOT is_blocked_between(owner, viewer) AND CASE COALESCE(
+        (SELECT status_visibility FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends' THEN EXISTS (
+            SELECT 1 FROM friends WHERE user_id = owner AND friend_id = viewer)
+        ELSE FALSE
+    END)
+$$;
+
+CREATE OR REPLACE FUNCTION can_send_friend_request(owner TEXT, requester TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT NOT is_blocked_between(owner, requester) AND CASE COALESCE(
+        (SELECT friend_requests FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends_of_friends' THEN EXISTS (
+            SELECT 1 FROM friends a
+            JOIN friends b ON b.user_id = a.friend_id
+            WHERE a.user_id = owner AND b.friend_id = requester)
+        ELSE FALSE
+    END
+$$;
+
+CREATE OR REPLACE FUNCTION can_start_dm(owner TEXT, requester TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT NOT is_blocked_between(owner, requester) AND CASE COALESCE(
+        (SELECT direct_messages FROM privacy_settings WHERE user_id = owner), 'everyone')
+        WHEN 'everyone' THEN TRUE
+        WHEN 'friends' THEN EXISTS (
+            SELECT 1 FROM friends WHERE user_id = owner AND friend_id = requester)
+        ELSE FALSE
+    END
+$$;
@@ -0,0 +1,51 @@
+-- Users allowed to review reports and act on them
+CREATE TABLE IF NOT EXISTS moderators (
+    user_id TEXT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- User and message reports. `snapshot` keeps the reported content as it was at report
+-- time, so later edits or deletes can't hide it. message_id deliberately has no FK.
+CREATE TABLE IF NOT EXISTS reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reporter_id TEXT NOT NULL,
+    target_type TEXT NOT NULL CHECK (target_type IN ('user', 'message')),
+    reported_user_id TEXT NOT NULL,
+    message_id UUID,
+    reason TEXT NOT NULL,
+    details TEXT,
+    snapshot JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'dismissed', 'warned', 'suspended')),
+    resolved_by TEXT,
+    resolution_note TEXT,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS reports_status_created_at_idx ON reports (status, created_at);
+
+-- One open report per reporter and target
+CREATE UNIQUE INDEX IF NOT EXISTS reports_open_message_idx
+    ON reports (reporter_id, message_id) WHERE status = 'open' AND target_type = 'message';
+CREATE UNIQUE INDEX IF NOT EXISTS reports_open_user_idx
+    ON reports (reporter_id, reported_user_id) WHERE status = 'open' AND target_type = 'user';
+
+CREATE TABLE IF NOT EXISTS user_warnings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    report_id UUID REFERENCES reports (id),
+    reason TEXT,
+    issued_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Active or past suspensions; NULL suspended_until means indefinite
+CREATE TABLE IF NOT EXISTS account_suspensions (
+    user_id TEXT PRIMARY KEY,
+    report_id UUID REFERENCES reports (id),
+    reason TEXT,
+    suspended_until TIMESTAMPTZ,
+    suspended_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,18 @@
+-- Drop duplicate friendship rows left by the old non-transactional accept
+DELETE FROM friends a
+USING friends b
+WHERE a.user_id = b.user_id AND a.friend_id = b.friend_id AND a.ctid > b.ctid;
+
+CREATE UNIQUE INDEX IF NOT EXISTS friends_user_friend_idx ON friends (user_id, friend_id);
+
+-- Keep only the oldest pending request per pair of users, in either direction
+DELETE FROM friend_requests a
+USING friend_requests b
+WHERE a.status = 'pending' AND b.status = 'pending'
+  AND LEAST(a.from_user_id, a.to_user_id) = LEAST(b.from_user_id, b.to_user_id)
+  AND GREATEST(a.from_user_id, a.to_user_id) = GREATEST(b.from_user_id, b.to_user_id)
+  AND (COALESCE(a.created_at, '-infinity'), a.id) > (COALESCE(b.created_at, '-infinity'), b.id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS friend_requests_pending_pair_idx
+    ON friend_requests (LEAST(from_user_id, to_user_id), GREATEST(from_user_id, to_user_id))
+    WHERE status = 'pending';
@@ -0,0 +1,11 @@
+-- Suggestions a user has dismissed; those users are never suggested to them again
+CREATE TABLE IF NOT EXISTS dismissed_suggestions (
+    user_id TEXT NOT NULL,
+    dismissed_user_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, dismissed_user_id)
+);
+
+-- Suggestion queries walk friends-of-friends and shared conversations by user
+CREATE INDEX IF NOT EXISTS conversation_participants_user_id_idx
+    ON conversation_participants (user_id, conversation_id);
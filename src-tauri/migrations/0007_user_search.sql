@@ -0,0 +1,15 @@
+-- Case-insensitive prefix and trigram search over usernames and nicknames
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS profiles_username_lower_prefix_idx
+    ON profiles (lower(username) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS profiles_nickname_lower_prefix_idx
+    ON profiles (lower(nickname) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS profiles_username_trgm_idx
+    ON profiles USING gin (lower(username) gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS profiles_nickname_trgm_idx
+    ON profiles USING gin (lower(nickname) gin_trgm_ops);
+
+-- Users can opt out of appearing in search for non-friends
+ALTER TABLE privacy_settings
+    ADD COLUMN IF NOT EXISTS searchable BOOLEAN NOT NULL DEFAULT TRUE;
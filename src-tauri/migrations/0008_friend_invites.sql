@@ -0,0 +1,16 @@
+-- Shareable friend invites. The token handed out is the random invite id; this row is
+-- what makes it expire, get used up or be revoked.
+CREATE TABLE IF NOT EXISTS friend_invites (
+    id UUID PRIMARY KEY,
+    inviter_id TEXT NOT NULL,
+    single_use BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Redeeming makes the two users friends immediately instead of sending a request
+    auto_accept BOOLEAN NOT NULL DEFAULT TRUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    used_by TEXT,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS friend_invites_inviter_id_idx ON friend_invites (inviter_id, created_at DESC);
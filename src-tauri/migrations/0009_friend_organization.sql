@@ -0,0 +1,19 @@
+-- User-defined groups for organizing a friends list ("Work", "Family", ...)
+CREATE TABLE IF NOT EXISTS friend_categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS friend_categories_user_name_idx
+    ON friend_categories (user_id, lower(name));
+
+-- Per-owner organization of each friendship. Only the `user_id` side of a row sees these.
+ALTER TABLE friends
+    ADD COLUMN IF NOT EXISTS is_favorite BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS category_id UUID REFERENCES friend_categories (id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS note TEXT,
+    ADD COLUMN IF NOT EXISTS local_nickname TEXT;
+
+CREATE INDEX IF NOT EXISTS friends_category_id_idx ON friends (category_id) WHERE category_id IS NOT NULL;
@@ -0,0 +1,21 @@
+-- Friend requests are kept as history instead of being deleted. Finished requests
+-- record when they were answered (or cancelled/expired) in responded_at.
+ALTER TABLE friend_requests
+    ADD COLUMN IF NOT EXISTS message TEXT,
+    ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS responded_at TIMESTAMPTZ;
+
+-- Give existing pending requests the default 30 day lifetime
+UPDATE friend_requests
+SET expires_at = COALESCE(created_at, NOW()) + INTERVAL '30 days'
+WHERE expires_at IS NULL AND status = 'pending';
+
+-- Sweeps look for overdue pending requests
+CREATE INDEX IF NOT EXISTS friend_requests_pending_expiry_idx
+    ON friend_requests (expires_at) WHERE status = 'pending';
+
+-- Decline cooldown lookups and history are per sender/recipient
+CREATE INDEX IF NOT EXISTS friend_requests_from_to_idx
+    ON friend_requests (from_user_id, to_user_id, responded_at DESC);
+CREATE INDEX IF NOT EXISTS friend_requests_to_user_id_idx
+    ON friend_requests (to_user_id, created_at DESC);
@@ -0,0 +1,8 @@
+-- Presence details shown on friends lists
+ALTER TABLE profiles
+    ADD COLUMN IF NOT EXISTS custom_status TEXT,
+    ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ;
+
+-- Unread counts scan a conversation's messages newer than the reader's last_read_at
+CREATE INDEX IF NOT EXISTS messages_conversation_timestamp_idx
+    ON messages (conversation_id, timestamp);
@@ -0,0 +1,14 @@
+-- One-time codes that let a user past MFA after losing their authenticator or phone.
+-- Only SHA-256 hashes are stored; the codes themselves are shown once. Owned by the
+-- account API and the user pool's auth triggers (see docs/account-api.md); the app
+-- never reads or writes it, but the schema lives with the rest of the database.
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS mfa_recovery_codes_user_code_idx
+    ON mfa_recovery_codes (user_id, code_hash);
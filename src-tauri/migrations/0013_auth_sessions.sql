@@ -0,0 +1,16 @@
+-- Sign-ins on each device, so users can see where they are signed in and revoke a
+-- session remotely. Rows are created and revoked by the account API, which keeps each
+-- device's refresh token outside this database and revokes it with Cognito; the app
+-- also stops acting for a revoked session on its next command.
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS auth_sessions_user_id_idx
+    ON auth_sessions (user_id, last_seen_at DESC)
+    WHERE revoked_at IS NULL;
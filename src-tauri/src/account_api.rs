@@ -0,0 +1,74 @@
+use crate::config::account_api_url;
+use reqwest::{Method, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// The account API is the server-side half of the app for anything the client must not be
+// trusted with (MFA recovery codes, revoking other devices). Every call is made as the
+// signed-in user, with their access token as the bearer token.
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// Call `path` on the account API. Only transport failures are errors; the caller
+/// decides what a non-success status means.
+pub(crate) async fn send<B: Serialize>(
+    method: Method,
+    path: &str,
+    access_token: &str,
+    body: Option<&B>,
+) -> Result<Response, String> {
+    let url = format!("{}{}", account_api_url()?, path);
+
+    let mut request = reqwest::Client::new()
+        .request(method, url)
+        .bearer_auth(access_token);
+    if let Some(body) = body {
+        request = request.json(body);
+    }
+
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Account API request failed: {}", e))
+}
+
+/// Message the account API returned with a failed response
+pub(crate) async fn error_message(response: Response) -> String {
+    let status = response.status();
+    let message = response
+        .json::<ApiError>()
+        .await
+        .map(|body| body.message)
+        .unwrap_or_else(|_| status.to_string());
+    format!("Account API request failed: {}", message)
+}
+
+/// Call `path` on the account API, treating any non-success status as an error
+pub(crate) async fn request<B: Serialize>(
+    method: Method,
+    path: &str,
+    access_token: &str,
+    body: Option<&B>,
+) -> Result<Response, String> {
+    let response = send(method, path, access_token, body).await?;
+    if !response.status().is_success() {
+        return Err(error_message(response).await);
+    }
+    Ok(response)
+}
+
+/// Call `path` on the account API and decode its JSON response
+pub(crate) async fn request_json<B: Serialize, T: DeserializeOwned>(
+    method: Method,
+    path: &str,
+    access_token: &str,
+    body: Option<&B>,
+) -> Result<T, String> {
+    request(method, path, access_token, body)
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected account API response: {}", e))
+}
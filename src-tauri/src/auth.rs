@@ -1,11 +1,41 @@
 use crate::config::{cognito_client_id, cognito_user_pool_id, aws_region};
+use crate::db::get_pool;
+use crate::jwt::{verify_access_token, verify_id_token};
+use crate::session_vault::{PersistedSession, SessionVault};
+use crate::sessions::{register_session, revoke_sessions, touch_session};
+use crate::srp::{srp_timestamp, SrpClient};
 use aws_sdk_cognitoidentityprovider::{
     Client as CognitoClient,
-    types::{AuthFlowType, AttributeType},
+    error::ProvideErrorMetadata,
+    operation::respond_to_auth_challenge::RespondToAuthChallengeOutput,
+    types::{AuthFlowType, AttributeType, AuthenticationResultType, ChallengeNameType},
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
-use tauri::{command, State};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Refresh tokens this long before the access token expires
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// Backoff between failed background refreshes: base delay, doubling up to the cap
+const REFRESH_RETRY_BASE: Duration = Duration::from_secs(2);
+const REFRESH_RETRY_MAX: Duration = Duration::from_secs(5 * 60);
+
+/// Minimum gap between confirmation codes sent to the same email
+const CONFIRMATION_RESEND_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How long a sign-up password is kept for signing in after confirmation
+const PENDING_SIGN_UP_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Cognito sign-in challenge sessions last three minutes
+const CHALLENGE_TTL: Duration = Duration::from_secs(3 * 60);
+
+/// Wrong codes accepted for one sign-in challenge before starting over
+pub(crate) const MAX_CHALLENGE_ATTEMPTS: u32 = 5;
 
 /// Represents a user session stored securely on the backend
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -16,19 +46,236 @@ pub struct Session {
     pub user_id: String,
     pub email: String,
     pub expires_at: i64,
+    /// Row in `auth_sessions` for this sign-in; empty if it couldn't be recorded
+    pub session_id: String,
 }
 
 /// Thread-safe session storage
 pub struct SessionStore {
     pub session: Mutex<Option<Session>>,
+    /// Encrypted on-disk copy of the session, opened in `setup` once the data dir is known
+    vault: OnceLock<SessionVault>,
+    /// Held while a persisted session is being restored at startup
+    restoring: Arc<tokio::sync::Mutex<()>>,
+    /// Held for the duration of a token refresh so concurrent callers share one
+    refreshing: tokio::sync::Mutex<()>,
+    /// Wakes the background refresh task whenever the session is replaced
+    session_changed: Notify,
+    /// Used to emit `session-expired`; set when the refresh task starts
+    app: OnceLock<AppHandle>,
+    /// Credentials of an account awaiting email confirmation, kept in memory only
+    /// so `confirm_sign_up` can sign the user straight in
+    pending_sign_up: Mutex<Option<PendingSignUp>>,
+    /// When a confirmation code was last sent to each email, for resend rate limiting
+    confirmation_sent_at: Mutex<HashMap<String, Instant>>,
+    /// MFA challenge from a sign-in that is waiting for the user's code
+    pending_challenge: Mutex<Option<PendingChallenge>>,
+}
+
+struct PendingSignUp {
+    email: String,
+    password: String,
+    created_at: Instant,
+}
+
+/// A sign-in paused on an MFA challenge. The password is kept so a recovery code
+/// can finish the sign-in once MFA has been reset.
+pub(crate) struct PendingChallenge {
+    pub username: String,
+    pub password: String,
+    pub name: ChallengeNameType,
+    pub session: String,
+    pub attempts: u32,
+    created_at: Instant,
+}
+
+/// What a token refresh did
+#[derive(Debug, PartialEq)]
+pub(crate) enum RefreshOutcome {
+    Refreshed,
+    /// Cognito rejected the refresh token, so the session was cleared
+    Rejected,
+    /// Nothing to refresh: signed out, or the session was replaced meanwhile
+    Skipped,
 }
 
 impl Default for SessionStore {
     fn default() -> Self {
         Self {
             session: Mutex::new(None),
+            vault: OnceLock::new(),
+            restoring: Arc::new(tokio::sync::Mutex::new(())),
+            refreshing: tokio::sync::Mutex::new(()),
+            session_changed: Notify::new(),
+            app: OnceLock::new(),
+            pending_sign_up: Mutex::new(None),
+            confirmation_sent_at: Mutex::new(HashMap::new()),
+            pending_challenge: Mutex::new(None),
+        }
+    }
+}
+
+impl SessionStore {
+    /// Replace the in-memory session and mirror it to the on-disk vault
+    pub(crate) fn set_session(&self, session: Option<Session>) -> Result<(), String> {
+        let mut store = self.session.lock().map_err(|e| e.to_string())?;
+        self.persist(session.as_ref());
+        *store = session;
+        self.session_changed.notify_one();
+        Ok(())
+    }
+
+    /// Like `set_session`, but only if the session still holds `refresh_token`, so a
+    /// refresh that finishes after a sign-out or new sign-in can't bring back the old session
+    fn replace_if_current(&self, refresh_token: &str, session: Option<Session>) -> Result<bool, String> {
+        let mut store = self.session.lock().map_err(|e| e.to_string())?;
+        if store.as_ref().is_none_or(|current| current.refresh_token != refresh_token) {
+            return Ok(false);
+        }
+        self.persist(session.as_ref());
+        *store = session;
+        self.session_changed.notify_one();
+        Ok(true)
+    }
+
+    fn persist(&self, session: Option<&Session>) {
+        if let Some(vault) = self.vault.get() {
+            let persisted = match session {
+                Some(session) if !session.refresh_token.is_empty() => {
+                    vault.save(&PersistedSession {
+                        refresh_token: session.refresh_token.clone(),
+                        user_id: session.user_id.clone(),
+                        email: session.email.clone(),
+                        session_id: session.session_id.clone(),
+                    })
+                }
+                _ => vault.clear(),
+            };
+            // A session that can't be persisted still works until the app closes
+            if let Err(e) = persisted {
+                eprintln!("Failed to persist session: {}", e);
+            }
+        }
+    }
+
+    /// Wait for any startup restore to finish before reading the session
+    async fn wait_for_restore(&self) {
+        let _ = self.restoring.lock().await;
+    }
+
+    /// The signed-in session if the access token is still valid.
+    /// Ok(None) means the session exists but has expired.
+    fn current_session(&self) -> Result<Option<Session>, String> {
+        let store = self
+            .session
+            .lock()
+            .map_err(|e| format!("Failed to lock session: {}", e))?;
+
+        match &*store {
+            Some(session) if chrono::Utc::now().timestamp() < session.expires_at => {
+                Ok(Some(session.clone()))
+            }
+            Some(_) => Ok(None),
+            None => Err("Not authenticated. Please sign in.".to_string()),
+        }
+    }
+
+    /// Id of the signed-in session, or empty if signed out
+    pub(crate) fn current_session_id(&self) -> String {
+        self.session
+            .lock()
+            .ok()
+            .and_then(|store| store.as_ref().map(|session| session.session_id.clone()))
+            .unwrap_or_default()
+    }
+
+    fn current_user_id(&self) -> Result<Option<String>, String> {
+        Ok(self.current_session()?.map(|session| session.user_id))
+    }
+
+    /// Update the email of the signed-in session after it changed in Cognito
+    fn set_email(&self, email: &str) -> Result<(), String> {
+        let mut store = self.session.lock().map_err(|e| e.to_string())?;
+        if let Some(session) = store.as_mut() {
+            session.email = email.to_string();
+            self.persist(Some(session));
+        }
+        Ok(())
+    }
+
+    /// Remember the password of an unconfirmed account until it is confirmed
+    fn set_pending_sign_up(&self, email: &str, password: &str) {
+        if let Ok(mut pending) = self.pending_sign_up.lock() {
+            *pending = Some(PendingSignUp {
+                email: email.trim().to_lowercase(),
+                password: password.to_string(),
+                created_at: Instant::now(),
+            });
+        }
+    }
+
+    /// Take the remembered password for `email`, if it hasn't expired.
+    /// Whatever was pending is cleared either way.
+    fn take_pending_sign_up(&self, email: &str) -> Option<String> {
+        let pending = self.pending_sign_up.lock().ok()?.take()?;
+        (pending.email == email.trim().to_lowercase()
+            && pending.created_at.elapsed() < PENDING_SIGN_UP_TTL)
+            .then_some(pending.password)
+    }
+
+    /// Record that a confirmation code is about to be sent to `email`.
+    /// Err holds the seconds left if one was sent too recently.
+    fn claim_confirmation_send(&self, email: &str) -> Result<(), u64> {
+        let Ok(mut sent_at) = self.confirmation_sent_at.lock() else {
+            return Ok(());
+        };
+        sent_at.retain(|_, at| at.elapsed() < CONFIRMATION_RESEND_COOLDOWN);
+
+        let email = email.trim().to_lowercase();
+        if let Some(at) = sent_at.get(&email) {
+            let remaining = CONFIRMATION_RESEND_COOLDOWN.saturating_sub(at.elapsed());
+            return Err(remaining.as_secs().max(1));
+        }
+        sent_at.insert(email, Instant::now());
+        Ok(())
+    }
+
+    /// Undo `claim_confirmation_send` after a send that failed, so the user can retry
+    fn release_confirmation_send(&self, email: &str) {
+        if let Ok(mut sent_at) = self.confirmation_sent_at.lock() {
+            sent_at.remove(&email.trim().to_lowercase());
+        }
+    }
+
+    fn set_pending_challenge(&self, challenge: Option<PendingChallenge>) {
+        if let Ok(mut pending) = self.pending_challenge.lock() {
+            *pending = challenge;
         }
     }
+
+    /// Take the challenge awaiting a code, unless it has expired
+    pub(crate) fn take_pending_challenge(&self) -> Option<PendingChallenge> {
+        self.pending_challenge
+            .lock()
+            .ok()?
+            .take()
+            .filter(|challenge| challenge.created_at.elapsed() < CHALLENGE_TTL)
+    }
+
+    /// Put a challenge back after a wrong code, unless it has run out of attempts
+    pub(crate) fn retry_pending_challenge(&self, mut challenge: PendingChallenge) {
+        challenge.attempts += 1;
+        if challenge.attempts < MAX_CHALLENGE_ATTEMPTS {
+            self.set_pending_challenge(Some(challenge));
+        }
+    }
+
+    fn expires_at(&self) -> Option<i64> {
+        self.session
+            .lock()
+            .ok()
+            .and_then(|store| store.as_ref().map(|session| session.expires_at))
+    }
 }
 
 /// Public session info returned to frontend (no sensitive tokens)
@@ -40,16 +287,91 @@ pub struct PublicSessionInfo {
 }
 
 /// Result returned to frontend for auth operations
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct AuthResult {
     pub success: bool,
     pub error: Option<String>,
     pub user_id: Option<String>,
     pub needs_confirmation: bool,
+    /// Set when sign-in needs a second factor; answer with `respond_to_mfa_challenge`
+    pub challenge: Option<AuthChallenge>,
+}
+
+/// Second factor a sign-in is waiting for
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthChallenge {
+    /// A code from the user's authenticator app
+    Totp,
+    /// A code texted to `destination` (a masked phone number)
+    Sms { destination: Option<String> },
+}
+
+/// Get the signed-in user's ID, rejecting expired sessions and suspended accounts.
+/// Every command that acts on behalf of a user goes through this check.
+pub(crate) async fn get_user_id_from_store(session_store: &SessionStore) -> Result<String, String> {
+    session_store.wait_for_restore().await;
+
+    let mut user_id = session_store.current_user_id()?;
+    if user_id.is_none() {
+        // The background refresh may not have run yet (e.g. after sleep); try once inline
+        if let Ok(RefreshOutcome::Refreshed) = refresh_tokens(session_store).await {
+            user_id = session_store.current_user_id()?;
+        }
+    }
+    let user_id = user_id.ok_or("Session expired. Please sign in again.")?;
+    let session_id = Uuid::parse_str(&session_store.current_session_id()).ok();
+
+    let pool = get_pool();
+    let (revoked, suspended, suspended_until): (bool, bool, Option<String>) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM auth_sessions WHERE id = $2 AND revoked_at IS NOT NULL),
+                s.user_id IS NOT NULL, s.suspended_until::text
+         FROM (SELECT 1) AS one
+         LEFT JOIN account_suspensions s
+           ON s.user_id = $1 AND (s.suspended_until IS NULL OR s.suspended_until > NOW())"
+    )
+    .bind(&user_id)
+    .bind(session_id)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    // Revoked from another device: its refresh token is already revoked, but the access
+    // token would otherwise keep working here until it expires
+    if revoked {
+        end_local_session(session_store).await?;
+        if let Some(app) = session_store.app.get() {
+            let _ = app.emit("session-expired", ());
+        }
+        return Err("This session was signed out from another device. Please sign in again.".to_string());
+    }
+
+    match (suspended, suspended_until) {
+        (true, Some(until)) => Err(format!("Your account is suspended until {}.", until)),
+        (true, None) => Err("Your account has been suspended.".to_string()),
+        (false, _) => Ok(user_id),
+    }
+}
+
+/// Get a valid access token for calls Cognito makes on the user's behalf,
+/// refreshing once inline if it has expired
+pub(crate) async fn get_access_token_from_store(session_store: &SessionStore) -> Result<String, String> {
+    session_store.wait_for_restore().await;
+
+    let mut session = session_store.current_session()?;
+    if session.is_none() {
+        if let Ok(RefreshOutcome::Refreshed) = refresh_tokens(session_store).await {
+            session = session_store.current_session()?;
+        }
+    }
+
+    session
+        .map(|session| session.access_token)
+        .ok_or_else(|| "Session expired. Please sign in again.".to_string())
 }
 
 /// Create Cognito client
-async fn create_cognito_client() -> CognitoClient {
+pub(crate) async fn create_cognito_client() -> CognitoClient {
     let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
         .region(aws_config::Region::new(aws_region()))
         .load()
@@ -57,6 +379,214 @@ async fn create_cognito_client() -> CognitoClient {
     CognitoClient::new(&config)
 }
 
+/// Record a fresh sign-in in `auth_sessions` and make it the current session
+pub(crate) async fn start_session(session_store: &SessionStore, mut session: Session) -> Result<(), String> {
+    // An untracked session still works; it just can't be listed or revoked remotely
+    session.session_id = match register_session(&session.access_token, &session.refresh_token).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            eprintln!("Failed to record session: {}", e);
+            String::new()
+        }
+    };
+    session_store.set_session(Some(session))
+}
+
+/// Store the tokens from a finished sign-in as the current session
+pub(crate) async fn complete_sign_in(
+    session_store: &SessionStore,
+    auth_result: &AuthenticationResultType,
+) -> Result<AuthResult, String> {
+    let session = match session_from_tokens(
+        auth_result.access_token().unwrap_or_default(),
+        auth_result.refresh_token().unwrap_or_default(),
+        auth_result.id_token().unwrap_or_default(),
+    )
+    .await
+    {
+        Ok(session) => session,
+        Err(e) => {
+            return Ok(AuthResult {
+                success: false,
+                error: Some(e),
+                user_id: None,
+                needs_confirmation: false,
+                challenge: None,
+            });
+        }
+    };
+    let user_id = session.user_id.clone();
+
+    start_session(session_store, session).await?;
+    session_store.set_pending_challenge(None);
+
+    Ok(AuthResult {
+        success: true,
+        error: None,
+        user_id: Some(user_id),
+        needs_confirmation: false,
+        challenge: None,
+    })
+}
+
+/// Hold on to an MFA challenge returned by `initiate_auth` and tell the frontend which code to ask for
+pub(crate) fn begin_challenge(
+    session_store: &SessionStore,
+    email: &str,
+    password: &str,
+    response: &RespondToAuthChallengeOutput,
+) -> AuthResult {
+    let params = response.challenge_parameters();
+    let (challenge, name) = match response.challenge_name() {
+        Some(name @ ChallengeNameType::SoftwareTokenMfa) => (AuthChallenge::Totp, name.clone()),
+        Some(name @ ChallengeNameType::SmsMfa) => (
+            AuthChallenge::Sms {
+                destination: params
+                    .and_then(|params| params.get("CODE_DELIVERY_DESTINATION"))
+                    .cloned(),
+            },
+            name.clone(),
+        ),
+        other => {
+            return AuthResult {
+                success: false,
+                error: Some(match other {
+                    Some(name) => format!("Unsupported sign-in challenge: {}", name.as_str()),
+                    None => "No authentication result".to_string(),
+                }),
+                user_id: None,
+                needs_confirmation: false,
+                challenge: None,
+            };
+        }
+    };
+
+    session_store.set_pending_challenge(Some(PendingChallenge {
+        // Challenge responses must name the user the way Cognito does, not by email alias
+        username: params
+            .and_then(|params| params.get("USER_ID_FOR_SRP"))
+            .cloned()
+            .unwrap_or_else(|| email.trim().to_string()),
+        password: password.to_string(),
+        name,
+        session: response.session().unwrap_or_default().to_string(),
+        attempts: 0,
+        created_at: Instant::now(),
+    }));
+
+    AuthResult {
+        success: false,
+        error: None,
+        user_id: None,
+        needs_confirmation: false,
+        challenge: Some(challenge),
+    }
+}
+
+/// A failed sign-in call, with the Cognito error code when there is one
+pub(crate) struct SignInError {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl SignInError {
+    fn from_sdk<E: ProvideErrorMetadata + std::fmt::Debug>(err: E) -> Self {
+        Self {
+            code: err.code().map(str::to_string),
+            message: format!("{:?}", err),
+        }
+    }
+}
+
+/// Prove the password to Cognito with SRP (`USER_SRP_AUTH`, then the `PASSWORD_VERIFIER`
+/// challenge) so it never leaves the device. The response holds either tokens or the
+/// next challenge, such as MFA.
+pub(crate) async fn srp_authenticate(
+    client: &CognitoClient,
+    username: &str,
+    password: &str,
+) -> Result<RespondToAuthChallengeOutput, SignInError> {
+    srp_authenticate_with_flow(client, AuthFlowType::UserSrpAuth, username, password).await
+}
+
+/// Like `srp_authenticate`, but through `CUSTOM_AUTH`: once the password is proven, the
+/// user pool's auth triggers decide what comes next, usually a `CUSTOM_CHALLENGE`
+pub(crate) async fn srp_custom_authenticate(
+    client: &CognitoClient,
+    username: &str,
+    password: &str,
+) -> Result<RespondToAuthChallengeOutput, SignInError> {
+    srp_authenticate_with_flow(client, AuthFlowType::CustomAuth, username, password).await
+}
+
+async fn srp_authenticate_with_flow(
+    client: &CognitoClient,
+    flow: AuthFlowType,
+    username: &str,
+    password: &str,
+) -> Result<RespondToAuthChallengeOutput, SignInError> {
+    let srp = SrpClient::new(&cognito_user_pool_id());
+
+    let mut request = client
+        .initiate_auth()
+        .client_id(cognito_client_id())
+        .auth_parameters("USERNAME", username)
+        .auth_parameters("SRP_A", srp.public_value_hex());
+    if flow == AuthFlowType::CustomAuth {
+        request = request.auth_parameters("CHALLENGE_NAME", "SRP_A");
+    }
+
+    let response = request
+        .auth_flow(flow)
+        .send()
+        .await
+        .map_err(|e| SignInError::from_sdk(e.into_service_error()))?;
+
+    let invalid = |message: &str| SignInError {
+        code: None,
+        message: message.to_string(),
+    };
+    if response.challenge_name() != Some(&ChallengeNameType::PasswordVerifier) {
+        return Err(invalid("Unexpected response to SRP sign-in"));
+    }
+    let params = response
+        .challenge_parameters()
+        .ok_or_else(|| invalid("SRP challenge is missing its parameters"))?;
+    let param = |name: &str| {
+        params
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| invalid(&format!("SRP challenge is missing {}", name)))
+    };
+
+    let user_id = param("USER_ID_FOR_SRP")?;
+    let secret_block = param("SECRET_BLOCK")?;
+    let timestamp = srp_timestamp(chrono::Utc::now());
+    let signature = srp
+        .password_claim_signature(
+            user_id,
+            password,
+            param("SALT")?,
+            param("SRP_B")?,
+            secret_block,
+            &timestamp,
+        )
+        .map_err(|e| invalid(&e))?;
+
+    client
+        .respond_to_auth_challenge()
+        .client_id(cognito_client_id())
+        .challenge_name(ChallengeNameType::PasswordVerifier)
+        .set_session(response.session().map(str::to_string))
+        .challenge_responses("USERNAME", user_id)
+        .challenge_responses("PASSWORD_CLAIM_SECRET_BLOCK", secret_block)
+        .challenge_responses("PASSWORD_CLAIM_SIGNATURE", signature)
+        .challenge_responses("TIMESTAMP", timestamp)
+        .send()
+        .await
+        .map_err(|e| SignInError::from_sdk(e.into_service_error()))
+}
+
 /// Tauri command to sign in with email and password
 #[command]
 pub async fn sign_in(
@@ -71,6 +601,7 @@ pub async fn sign_in(
             error: Some("Email is required".to_string()),
             user_id: None,
             needs_confirmation: false,
+            challenge: None,
         });
     }
 
@@ -80,73 +611,38 @@ pub async fn sign_in(
             error: Some("Password is required".to_string()),
             user_id: None,
             needs_confirmation: false,
+            challenge: None,
         });
     }
 
     let client = create_cognito_client().await;
 
-    let result = client
-        .initiate_auth()
-        .auth_flow(AuthFlowType::UserPasswordAuth)
-        .client_id(cognito_client_id())
-        .auth_parameters("USERNAME", email.trim())
-        .auth_parameters("PASSWORD", &password)
-        .send()
-        .await;
-
-    match result {
+    match srp_authenticate(&client, email.trim(), &password).await {
         Ok(response) => {
-            if let Some(auth_result) = response.authentication_result() {
-                let access_token = auth_result.access_token().unwrap_or_default().to_string();
-                let refresh_token = auth_result.refresh_token().unwrap_or_default().to_string();
-                let id_token = auth_result.id_token().unwrap_or_default().to_string();
-                let expires_in = auth_result.expires_in() as i64;
-
-                // Decode user info from ID token (JWT)
-                let (user_id, user_email) = decode_id_token(&id_token);
-
-                let expires_at = chrono::Utc::now().timestamp() + expires_in;
-
-                let session = Session {
-                    access_token,
-                    refresh_token,
-                    id_token,
-                    user_id: user_id.clone(),
-                    email: user_email,
-                    expires_at,
-                };
-
-                let mut store = session_store.session.lock().map_err(|e| e.to_string())?;
-                *store = Some(session);
-
-                Ok(AuthResult {
-                    success: true,
-                    error: None,
-                    user_id: Some(user_id),
-                    needs_confirmation: false,
-                })
-            } else {
-                Ok(AuthResult {
-                    success: false,
-                    error: Some("No authentication result".to_string()),
-                    user_id: None,
-                    needs_confirmation: false,
-                })
+            let result = match response.authentication_result() {
+                Some(auth_result) => complete_sign_in(&session_store, auth_result).await?,
+                None => begin_challenge(&session_store, &email, &password, &response),
+            };
+            if result.success {
+                session_store.take_pending_sign_up(&email);
             }
+            Ok(result)
         }
-        Err(e) => {
-            let error_message = match e.into_service_error() {
-                err if err.is_not_authorized_exception() => "Invalid email or password".to_string(),
-                err if err.is_user_not_found_exception() => "User not found".to_string(),
-                err if err.is_user_not_confirmed_exception() => {
+        Err(err) => {
+            let error_message = match err.code.as_deref() {
+                Some("NotAuthorizedException") => "Invalid email or password".to_string(),
+                Some("UserNotFoundException") => "User not found".to_string(),
+                Some("UserNotConfirmedException") => {
+                    session_store.set_pending_sign_up(&email, &password);
                     return Ok(AuthResult {
                         success: false,
                         error: Some("Please confirm your email first".to_string()),
                         user_id: None,
                         needs_confirmation: true,
+                        challenge: None,
                     });
                 }
-                err => format!("Authentication failed: {:?}", err),
+                _ => format!("Authentication failed: {}", err.message),
             };
 
             Ok(AuthResult {
@@ -154,6 +650,7 @@ pub async fn sign_in(
                 error: Some(error_message),
                 user_id: None,
                 needs_confirmation: false,
+                challenge: None,
             })
         }
     }
@@ -174,6 +671,7 @@ pub async fn sign_up(
             error: Some("Email is required".to_string()),
             user_id: None,
             needs_confirmation: false,
+            challenge: None,
         });
     }
 
@@ -183,6 +681,7 @@ pub async fn sign_up(
             error: Some("Password must be at least 8 characters".to_string()),
             user_id: None,
             needs_confirmation: false,
+            challenge: None,
         });
     }
 
@@ -228,11 +727,16 @@ pub async fn sign_up(
                 return sign_in(email, password, session_store).await;
             }
 
+            // Cognito just emailed a code, which counts against the resend limit
+            session_store.set_pending_sign_up(&email, &password);
+            let _ = session_store.claim_confirmation_send(&email);
+
             Ok(AuthResult {
                 success: true,
                 error: None,
                 user_id: Some(user_id),
                 needs_confirmation: true,
+                challenge: None,
             })
         }
         Err(e) => {
@@ -251,16 +755,20 @@ pub async fn sign_up(
                 error: Some(error_message),
                 user_id: None,
                 needs_confirmation: false,
+                challenge: None,
             })
         }
     }
 }
 
-/// Confirm signup with verification code
+/// Confirm signup with verification code. If the password from sign-up (or from a
+/// sign-in attempt on the unconfirmed account) is still held, the user is signed in
+/// and `user_id` is set.
 #[command]
 pub async fn confirm_sign_up(
     email: String,
     code: String,
+    session_store: State<'_, SessionStore>,
 ) -> Result<AuthResult, String> {
     let client = create_cognito_client().await;
 
@@ -268,17 +776,28 @@ pub async fn confirm_sign_up(
         .confirm_sign_up()
         .client_id(cognito_client_id())
         .username(email.trim())
-        .confirmation_code(&code)
+        .confirmation_code(code.trim())
         .send()
         .await;
 
     match result {
-        Ok(_) => Ok(AuthResult {
-            success: true,
-            error: None,
-            user_id: None,
-            needs_confirmation: false,
-        }),
+        Ok(_) => {
+            if let Some(password) = session_store.take_pending_sign_up(&email) {
+                let signed_in = sign_in(email, password, session_store).await?;
+                if signed_in.success {
+                    return Ok(signed_in);
+                }
+            }
+
+            // Confirmed, but the user has to sign in themselves
+            Ok(AuthResult {
+                success: true,
+                error: None,
+                user_id: None,
+                needs_confirmation: false,
+                challenge: None,
+            })
+        }
         Err(e) => {
             let error_message = match e.into_service_error() {
                 err if err.is_code_mismatch_exception() => "Invalid verification code".to_string(),
@@ -291,191 +810,857 @@ pub async fn confirm_sign_up(
                 error: Some(error_message),
                 user_id: None,
                 needs_confirmation: true,
+                challenge: None,
             })
         }
     }
 }
 
-/// Tauri command to sign out and clear the session
-#[command]
-pub async fn sign_out(session_store: State<'_, SessionStore>) -> Result<bool, String> {
-    let mut store = session_store.session.lock().map_err(|e| e.to_string())?;
-    *store = None;
-    Ok(true)
-}
-
-/// Tauri command to get current session info (without exposing tokens)
-#[command]
-pub async fn get_session(
-    session_store: State<'_, SessionStore>,
-) -> Result<Option<PublicSessionInfo>, String> {
-    let store = session_store.session.lock().map_err(|e| e.to_string())?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Ok(None)
-            } else {
-                Ok(Some(PublicSessionInfo {
-                    user_id: session.user_id.clone(),
-                    email: session.email.clone(),
-                    is_authenticated: true,
-                }))
-            }
-        }
-        None => Ok(None),
-    }
-}
-
-/// Tauri command to get the auth token for API calls
-#[command]
-pub async fn get_auth_token(
-    session_store: State<'_, SessionStore>,
-) -> Result<Option<String>, String> {
-    let store = session_store.session.lock().map_err(|e| e.to_string())?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Ok(None)
-            } else {
-                Ok(Some(session.access_token.clone()))
-            }
-        }
-        None => Ok(None),
-    }
-}
-
-/// Tauri command to get user ID from current session
+/// Start a password reset by emailing a reset code. Succeeds whether or not the
+/// email has an account, so the response can't be used to discover accounts.
 #[command]
-pub async fn get_user_id(
-    session_store: State<'_, SessionStore>,
-) -> Result<Option<String>, String> {
-    let store = session_store.session.lock().map_err(|e| e.to_string())?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Ok(None)
-            } else {
-                Ok(Some(session.user_id.clone()))
-            }
-        }
-        None => Ok(None),
+pub async fn forgot_password(email: String) -> Result<AuthResult, String> {
+    if email.trim().is_empty() {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Email is required".to_string()),
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        });
     }
-}
-
-/// Tauri command to refresh the session token
-#[command]
-pub async fn refresh_session(session_store: State<'_, SessionStore>) -> Result<bool, String> {
-    let refresh_token = {
-        let store = session_store.session.lock().map_err(|e| e.to_string())?;
-        match &*store {
-            Some(session) => session.refresh_token.clone(),
-            None => return Ok(false),
-        }
-    };
 
     let client = create_cognito_client().await;
 
     let result = client
-        .initiate_auth()
-        .auth_flow(AuthFlowType::RefreshTokenAuth)
+        .forgot_password()
         .client_id(cognito_client_id())
-        .auth_parameters("REFRESH_TOKEN", &refresh_token)
+        .username(email.trim())
         .send()
         .await;
 
-    match result {
-        Ok(response) => {
-            if let Some(auth_result) = response.authentication_result() {
-                let access_token = auth_result.access_token().unwrap_or_default().to_string();
-                let id_token = auth_result.id_token().unwrap_or_default().to_string();
-                let expires_in = auth_result.expires_in() as i64;
-
-                let (user_id, user_email) = decode_id_token(&id_token);
-                let expires_at = chrono::Utc::now().timestamp() + expires_in;
-
-                let mut store = session_store.session.lock().map_err(|e| e.to_string())?;
-                if let Some(session) = store.as_mut() {
-                    session.access_token = access_token;
-                    session.id_token = id_token;
-                    session.user_id = user_id;
-                    session.email = user_email;
-                    session.expires_at = expires_at;
-                }
-
-                Ok(true)
-            } else {
-                Ok(false)
+    let error_message = match result {
+        Ok(_) => None,
+        Err(e) => match e.into_service_error() {
+            // Unknown and unconfirmed accounts look exactly like a sent code
+            err if err.is_user_not_found_exception()
+                || err.is_not_authorized_exception()
+                || err.is_invalid_parameter_exception() => None,
+            err if err.is_limit_exceeded_exception() || err.is_too_many_requests_exception() => {
+                Some("Too many attempts. Please try again later".to_string())
             }
+            err => Some(format!("Password reset failed: {:?}", err)),
+        },
+    };
+
+    Ok(AuthResult {
+        success: error_message.is_none(),
+        needs_confirmation: error_message.is_none(),
+        challenge: None,
+        error: error_message,
+        user_id: None,
+    })
+}
+
+/// Finish a password reset with the emailed code and a new password
+#[command]
+pub async fn confirm_forgot_password(
+    email: String,
+    code: String,
+    new_password: String,
+) -> Result<AuthResult, String> {
+    if new_password.len() < 8 {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Password must be at least 8 characters".to_string()),
+            user_id: None,
+            needs_confirmation: true,
+            challenge: None,
+        });
+    }
+
+    let client = create_cognito_client().await;
+
+    let result = client
+        .confirm_forgot_password()
+        .client_id(cognito_client_id())
+        .username(email.trim())
+        .confirmation_code(code.trim())
+        .password(&new_password)
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(AuthResult {
+            success: true,
+            error: None,
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        }),
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                // An unknown email gets the same answer as a wrong code
+                err if err.is_code_mismatch_exception()
+                    || err.is_user_not_found_exception()
+                    || err.is_not_authorized_exception() => "Invalid verification code".to_string(),
+                err if err.is_expired_code_exception() => "Verification code has expired".to_string(),
+                err if err.is_invalid_password_exception() => "Password does not meet requirements".to_string(),
+                err if err.is_limit_exceeded_exception()
+                    || err.is_too_many_failed_attempts_exception()
+                    || err.is_too_many_requests_exception() => {
+                    "Too many attempts. Please try again later".to_string()
+                }
+                err => format!("Password reset failed: {:?}", err),
+            };
+
+            Ok(AuthResult {
+                success: false,
+                error: Some(error_message),
+                user_id: None,
+                needs_confirmation: true,
+                challenge: None,
+            })
         }
-        Err(_) => {
-            let mut store = session_store.session.lock().map_err(|e| e.to_string())?;
-            *store = None;
-            Ok(false)
+    }
+}
+
+/// Change the signed-in user's password
+#[command]
+pub async fn change_password(
+    old_password: String,
+    new_password: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    if new_password.len() < 8 {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Password must be at least 8 characters".to_string()),
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        });
+    }
+
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .change_password()
+        .access_token(access_token)
+        .previous_password(&old_password)
+        .proposed_password(&new_password)
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(AuthResult {
+            success: true,
+            error: None,
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        }),
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                err if err.is_not_authorized_exception() => "Current password is incorrect".to_string(),
+                err if err.is_invalid_password_exception() => "Password does not meet requirements".to_string(),
+                err if err.is_limit_exceeded_exception() || err.is_too_many_requests_exception() => {
+                    "Too many attempts. Please try again later".to_string()
+                }
+                err => format!("Password change failed: {:?}", err),
+            };
+
+            Ok(AuthResult {
+                success: false,
+                error: Some(error_message),
+                user_id: None,
+                needs_confirmation: false,
+                challenge: None,
+            })
         }
     }
 }
 
-/// Decode user info from Cognito ID token (JWT)
-fn decode_id_token(id_token: &str) -> (String, String) {
-    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
-    
-    // JWT has 3 parts separated by dots: header.payload.signature
-    let parts: Vec<&str> = id_token.split('.').collect();
-    if parts.len() != 3 {
-        return (String::new(), String::new());
+/// Log when the user pool applied an attribute change before it was verified, which
+/// means the pool is missing the setting `change_email` relies on
+async fn warn_if_applied_unverified(client: &CognitoClient, access_token: &str, name: &str, value: &str) {
+    let Ok(user) = client.get_user().access_token(access_token).send().await else {
+        return;
+    };
+
+    let current = user
+        .user_attributes()
+        .iter()
+        .find(|attribute| attribute.name() == name)
+        .and_then(|attribute| attribute.value());
+    if current == Some(value) {
+        eprintln!(
+            "User pool replaced {} before it was verified; add it to the pool's \
+             AttributesRequireVerificationBeforeUpdate",
+            name
+        );
     }
+}
 
-    // Decode the payload (second part)
-    if let Ok(decoded) = URL_SAFE_NO_PAD.decode(parts[1]) {
-        if let Ok(payload) = String::from_utf8(decoded) {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&payload) {
-                let user_id = json["sub"].as_str().unwrap_or_default().to_string();
-                let email = json["email"].as_str().unwrap_or_default().to_string();
-                return (user_id, email);
-            }
+/// Set a user attribute that Cognito verifies with a code (email or phone number).
+/// On success the code has been sent and `needs_confirmation` is set.
+async fn update_verified_attribute(
+    session_store: &SessionStore,
+    name: &str,
+    value: &str,
+) -> Result<AuthResult, String> {
+    let access_token = get_access_token_from_store(session_store).await?;
+    let client = create_cognito_client().await;
+
+    let attribute = AttributeType::builder()
+        .name(name)
+        .value(value)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let result = client
+        .update_user_attributes()
+        .access_token(&access_token)
+        .user_attributes(attribute)
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => {
+            warn_if_applied_unverified(&client, &access_token, name, value).await;
+            Ok(AuthResult {
+                success: true,
+                error: None,
+                user_id: None,
+                needs_confirmation: true,
+                challenge: None,
+            })
         }
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                err if err.is_alias_exists_exception() => {
+                    format!("Another account already uses this {}", name.replace('_', " "))
+                }
+                err if err.is_invalid_parameter_exception() => {
+                    err.meta().message().unwrap_or("Invalid parameter").to_string()
+                }
+                err if err.is_code_delivery_failure_exception() => {
+                    "Could not send the verification code".to_string()
+                }
+                err if err.is_too_many_requests_exception() => {
+                    "Too many attempts. Please try again later".to_string()
+                }
+                err => format!("Update failed: {:?}", err),
+            };
+
+            Ok(AuthResult {
+                success: false,
+                error: Some(error_message),
+                user_id: None,
+                needs_confirmation: false,
+                challenge: None,
+            })
+        }
+    }
+}
+
+/// Confirm a pending attribute change with the code Cognito sent
+async fn verify_attribute(
+    session_store: &SessionStore,
+    name: &str,
+    code: &str,
+) -> Result<AuthResult, String> {
+    let access_token = get_access_token_from_store(session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .verify_user_attribute()
+        .access_token(access_token)
+        .attribute_name(name)
+        .code(code.trim())
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(AuthResult {
+            success: true,
+            error: None,
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        }),
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                err if err.is_code_mismatch_exception() => "Invalid verification code".to_string(),
+                err if err.is_expired_code_exception() => "Verification code has expired".to_string(),
+                err if err.is_alias_exists_exception() => {
+                    format!("Another account already uses this {}", name.replace('_', " "))
+                }
+                err if err.is_limit_exceeded_exception() || err.is_too_many_requests_exception() => {
+                    "Too many attempts. Please try again later".to_string()
+                }
+                err => format!("Verification failed: {:?}", err),
+            };
+
+            Ok(AuthResult {
+                success: false,
+                error: Some(error_message),
+                user_id: None,
+                needs_confirmation: true,
+                challenge: None,
+            })
+        }
+    }
+}
+
+/// Start changing the signed-in user's email. A code is sent to the new address;
+/// finish with `confirm_email_change`.
+///
+/// Requires the user pool to keep the original value active while an update is pending
+/// (`UserAttributeUpdateSettings.AttributesRequireVerificationBeforeUpdate` including
+/// `email` and `phone_number`). Without it Cognito swaps the email straight away, and
+/// the old address stops working for sign-in before the new one is verified.
+#[command]
+pub async fn change_email(
+    new_email: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    if new_email.trim().is_empty() {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Email is required".to_string()),
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        });
+    }
+
+    update_verified_attribute(&session_store, "email", new_email.trim()).await
+}
+
+/// Confirm an email change with the code sent to the new address
+#[command]
+pub async fn confirm_email_change(
+    code: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    let result = verify_attribute(&session_store, "email", &code).await?;
+    if !result.success {
+        return Ok(result);
+    }
+
+    // New tokens carry the new email; fall back to asking Cognito if the refresh fails
+    if let Ok(RefreshOutcome::Refreshed) = refresh_tokens(&session_store).await {
+        return Ok(result);
     }
 
-    (String::new(), String::new())
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+    if let Ok(user) = client.get_user().access_token(access_token).send().await {
+        if let Some(email) = user
+            .user_attributes()
+            .iter()
+            .find(|attribute| attribute.name() == "email")
+            .and_then(|attribute| attribute.value())
+        {
+            session_store.set_email(email)?;
+        }
+    }
+
+    Ok(result)
 }
 
-/// Sync OAuth session (for Google sign-in via hosted UI)
+/// Set the signed-in user's phone number. A code is sent by SMS;
+/// finish with `verify_phone_number`.
 #[command]
-pub async fn sync_oauth_session(
-    access_token: String,
-    refresh_token: String,
-    id_token: String,
-    user_id: String,
+pub async fn update_phone_number(
+    phone_number: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    if phone_number.trim().is_empty() {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Phone number is required".to_string()),
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        });
+    }
+
+    update_verified_attribute(&session_store, "phone_number", phone_number.trim()).await
+}
+
+/// Verify the signed-in user's phone number with the code sent by SMS
+#[command]
+pub async fn verify_phone_number(
+    code: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    verify_attribute(&session_store, "phone_number", &code).await
+}
+
+/// Send a new sign-up confirmation code, at most once a minute per email.
+/// Unknown and already-confirmed emails get the same response as a real send, and a
+/// failed send doesn't start the cooldown.
+#[command]
+pub async fn resend_confirmation_code(
     email: String,
-    expires_at: i64,
     session_store: State<'_, SessionStore>,
-) -> Result<bool, String> {
-    if access_token.is_empty() {
-        return Err("Access token is required".to_string());
+) -> Result<AuthResult, String> {
+    if email.trim().is_empty() {
+        return Ok(AuthResult {
+            success: false,
+            error: Some("Email is required".to_string()),
+            user_id: None,
+            needs_confirmation: true,
+            challenge: None,
+        });
     }
 
-    if user_id.is_empty() {
-        return Err("User ID is required".to_string());
+    if let Err(wait_secs) = session_store.claim_confirmation_send(&email) {
+        return Ok(AuthResult {
+            success: false,
+            error: Some(format!(
+                "Please wait {} seconds before requesting another code",
+                wait_secs
+            )),
+            user_id: None,
+            needs_confirmation: true,
+            challenge: None,
+        });
     }
 
-    let session = Session {
-        access_token,
-        refresh_token,
-        id_token,
-        user_id,
-        email,
-        expires_at,
+    let client = create_cognito_client().await;
+
+    let result = client
+        .resend_confirmation_code()
+        .client_id(cognito_client_id())
+        .username(email.trim())
+        .send()
+        .await;
+
+    let error_message = match result {
+        Ok(_) => None,
+        Err(e) => match e.into_service_error() {
+            err if err.is_user_not_found_exception() || err.is_invalid_parameter_exception() => None,
+            err if err.is_code_delivery_failure_exception() => {
+                Some("Could not send the verification code".to_string())
+            }
+            err if err.is_limit_exceeded_exception() || err.is_too_many_requests_exception() => {
+                Some("Too many attempts. Please try again later".to_string())
+            }
+            err => Some(format!("Failed to resend code: {:?}", err)),
+        },
     };
 
-    let mut store = session_store.session.lock().map_err(|e| e.to_string())?;
-    *store = Some(session);
+    if error_message.is_some() {
+        session_store.release_confirmation_send(&email);
+    }
+
+    Ok(AuthResult {
+        success: error_message.is_none(),
+        error: error_message,
+        user_id: None,
+        needs_confirmation: true,
+        challenge: None,
+    })
+}
+
+/// Revoke a refresh token (and the access tokens minted from it). Best effort: a
+/// failure is logged, since the local session is cleared either way.
+async fn revoke_refresh_token(refresh_token: &str) {
+    if refresh_token.is_empty() {
+        return;
+    }
+
+    let client = create_cognito_client().await;
+    let result = client
+        .revoke_token()
+        .client_id(cognito_client_id())
+        .token(refresh_token)
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        eprintln!("Failed to revoke refresh token: {:?}", e.into_service_error());
+    }
+}
 
+/// Clear the local session and everything tied to it
+fn clear_local_session(session_store: &SessionStore) -> Result<(), String> {
+    session_store.set_session(None)?;
+    if let Ok(mut pending) = session_store.pending_sign_up.lock() {
+        *pending = None;
+    }
+    session_store.set_pending_challenge(None);
+    Ok(())
+}
+
+/// Sign this device out: revoke its refresh token with Cognito, mark its
+/// `auth_sessions` row revoked and clear the local session
+pub(crate) async fn end_local_session(session_store: &SessionStore) -> Result<(), String> {
+    let current = session_store
+        .session
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    clear_local_session(session_store)?;
+
+    if let Some(session) = current {
+        revoke_refresh_token(&session.refresh_token).await;
+        if !session.session_id.is_empty() {
+            if let Err(e) = revoke_sessions(&session.user_id, Some(&session.session_id)).await {
+                eprintln!("Failed to end session: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tauri command to sign out, revoking the refresh token and clearing the session,
+/// including the stored copy
+#[command]
+pub async fn sign_out(session_store: State<'_, SessionStore>) -> Result<bool, String> {
+    end_local_session(&session_store).await?;
     Ok(true)
 }
 
+/// Sign out on every device: Cognito invalidates all of the user's refresh tokens
+#[command]
+pub async fn sign_out_everywhere(session_store: State<'_, SessionStore>) -> Result<AuthResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .global_sign_out()
+        .access_token(access_token)
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        let error_message = match e.into_service_error() {
+            err if err.is_too_many_requests_exception() => {
+                "Too many attempts. Please try again later".to_string()
+            }
+            err => format!("Sign out failed: {:?}", err),
+        };
+        return Ok(AuthResult {
+            success: false,
+            error: Some(error_message),
+            user_id: None,
+            needs_confirmation: false,
+            challenge: None,
+        });
+    }
+
+    if let Err(e) = revoke_sessions(&user_id, None).await {
+        eprintln!("Failed to end sessions: {}", e);
+    }
+    clear_local_session(&session_store)?;
+
+    Ok(AuthResult {
+        success: true,
+        error: None,
+        user_id: None,
+        needs_confirmation: false,
+        challenge: None,
+    })
+}
+
+/// Tauri command to get current session info (without exposing tokens)
+#[command]
+pub async fn get_session(
+    session_store: State<'_, SessionStore>,
+) -> Result<Option<PublicSessionInfo>, String> {
+    session_store.wait_for_restore().await;
+    let store = session_store.session.lock().map_err(|e| e.to_string())?;
+
+    match &*store {
+        Some(session) => {
+            if chrono::Utc::now().timestamp() >= session.expires_at {
+                Ok(None)
+            } else {
+                Ok(Some(PublicSessionInfo {
+                    user_id: session.user_id.clone(),
+                    email: session.email.clone(),
+                    is_authenticated: true,
+                }))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+/// Tauri command to get the auth token for API calls
+#[command]
+pub async fn get_auth_token(
+    session_store: State<'_, SessionStore>,
+) -> Result<Option<String>, String> {
+    let store = session_store.session.lock().map_err(|e| e.to_string())?;
+
+    match &*store {
+        Some(session) => {
+            if chrono::Utc::now().timestamp() >= session.expires_at {
+                Ok(None)
+            } else {
+                Ok(Some(session.access_token.clone()))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+/// Tauri command to get user ID from current session
+#[command]
+pub async fn get_user_id(
+    session_store: State<'_, SessionStore>,
+) -> Result<Option<String>, String> {
+    let store = session_store.session.lock().map_err(|e| e.to_string())?;
+
+    match &*store {
+        Some(session) => {
+            if chrono::Utc::now().timestamp() >= session.expires_at {
+                Ok(None)
+            } else {
+                Ok(Some(session.user_id.clone()))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+/// Exchange the refresh token for fresh access and ID tokens. Refreshes are serialized:
+/// a caller that waited on another refresh reuses its result instead of refreshing again.
+/// Err is a transient failure (network, throttling) that leaves the session intact.
+pub(crate) async fn refresh_tokens(session_store: &SessionStore) -> Result<RefreshOutcome, String> {
+    let seen_expires_at = session_store.expires_at();
+    let _refreshing = session_store.refreshing.lock().await;
+
+    let current = {
+        let store = session_store.session.lock().map_err(|e| e.to_string())?;
+        match &*store {
+            Some(session) => session.clone(),
+            None => return Ok(RefreshOutcome::Skipped),
+        }
+    };
+
+    if Some(current.expires_at) != seen_expires_at {
+        return Ok(RefreshOutcome::Refreshed);
+    }
+
+    // A session revoked from another device is signed out here. If the check itself
+    // fails, refresh anyway rather than signing the user out over a database hiccup.
+    if !current.session_id.is_empty() {
+        if let Ok(false) = touch_session(&current.session_id).await {
+            revoke_refresh_token(&current.refresh_token).await;
+            return expire_session(session_store, &current.refresh_token);
+        }
+    }
+
+    let client = create_cognito_client().await;
+
+    let result = client
+        .initiate_auth()
+        .auth_flow(AuthFlowType::RefreshTokenAuth)
+        .client_id(cognito_client_id())
+        .auth_parameters("REFRESH_TOKEN", &current.refresh_token)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => {
+            let Some(auth_result) = response.authentication_result() else {
+                return Err("No authentication result".to_string());
+            };
+
+            // Cognito only returns a new refresh token when rotation is enabled
+            let refresh_token = auth_result
+                .refresh_token()
+                .filter(|token| !token.is_empty())
+                .unwrap_or(&current.refresh_token);
+            let session = Session {
+                session_id: current.session_id.clone(),
+                ..session_from_tokens(
+                    auth_result.access_token().unwrap_or_default(),
+                    refresh_token,
+                    auth_result.id_token().unwrap_or_default(),
+                )
+                .await?
+            };
+
+            if session_store.replace_if_current(&current.refresh_token, Some(session))? {
+                Ok(RefreshOutcome::Refreshed)
+            } else {
+                Ok(RefreshOutcome::Skipped)
+            }
+        }
+        Err(e) => match e.into_service_error() {
+            err if err.is_not_authorized_exception() || err.is_user_not_found_exception() => {
+                expire_session(session_store, &current.refresh_token)
+            }
+            err => Err(format!("Token refresh failed: {:?}", err)),
+        },
+    }
+}
+
+/// Clear a session whose refresh token is no longer usable and tell the frontend
+fn expire_session(session_store: &SessionStore, refresh_token: &str) -> Result<RefreshOutcome, String> {
+    if !session_store.replace_if_current(refresh_token, None)? {
+        return Ok(RefreshOutcome::Skipped);
+    }
+    if let Some(app) = session_store.app.get() {
+        let _ = app.emit("session-expired", ());
+    }
+    Ok(RefreshOutcome::Rejected)
+}
+
+/// Delay before the next background refresh attempt after `failures` transient failures
+fn refresh_backoff(failures: u32) -> Duration {
+    REFRESH_RETRY_BASE
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(REFRESH_RETRY_MAX)
+}
+
+/// Start the task that refreshes tokens shortly before they expire. It sleeps until the
+/// refresh is due or the session changes, and retries transient failures with backoff.
+/// `session-expired` is only emitted when Cognito rejects the refresh token.
+pub(crate) fn spawn_token_refresh(app: &AppHandle) {
+    let _ = app.state::<SessionStore>().app.set(app.clone());
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let session_store = app.state::<SessionStore>();
+        let mut failures: u32 = 0;
+
+        loop {
+            let Some(expires_at) = session_store.expires_at() else {
+                // Signed out: wait for the next sign-in
+                session_store.session_changed.notified().await;
+                failures = 0;
+                continue;
+            };
+
+            let due_in = if failures > 0 {
+                refresh_backoff(failures)
+            } else {
+                let secs = expires_at - chrono::Utc::now().timestamp() - REFRESH_MARGIN_SECS;
+                Duration::from_secs(secs.max(0) as u64)
+            };
+
+            if !due_in.is_zero() {
+                tokio::select! {
+                    _ = tokio::time::sleep(due_in) => {}
+                    _ = session_store.session_changed.notified() => {
+                        failures = 0;
+                        continue;
+                    }
+                }
+            }
+
+            match refresh_tokens(&session_store).await {
+                Ok(_) => failures = 0,
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("Background token refresh failed (attempt {}): {}", failures, e);
+                }
+            }
+        }
+    });
+}
+
+/// Tauri command to refresh the session token
+#[command]
+pub async fn refresh_session(session_store: State<'_, SessionStore>) -> Result<bool, String> {
+    match refresh_tokens(&session_store).await {
+        Ok(outcome) => Ok(outcome == RefreshOutcome::Refreshed),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(false)
+        }
+    }
+}
+
+/// Open the session vault and, if a session was saved last run, restore it and refresh
+/// its tokens in the background. Called once from `setup`.
+pub(crate) fn restore_persisted_session(app: &AppHandle) {
+    let session_store = app.state::<SessionStore>();
+
+    let vault = match app.path().app_data_dir() {
+        Ok(dir) => SessionVault::open_in_dir(&dir),
+        Err(e) => Err(format!("Failed to resolve app data dir: {}", e)),
+    };
+    let vault = match vault {
+        Ok(vault) => vault,
+        Err(e) => {
+            eprintln!("Session persistence unavailable: {}", e);
+            return;
+        }
+    };
+
+    let persisted = vault.load();
+    let _ = session_store.vault.set(vault);
+
+    let Some(persisted) = persisted else {
+        return;
+    };
+
+    // Commands wait on this until the refresh below settles
+    let Ok(restoring) = session_store.restoring.clone().try_lock_owned() else {
+        return;
+    };
+
+    // Access and ID tokens weren't stored, so the session starts out expired
+    if let Ok(mut store) = session_store.session.lock() {
+        *store = Some(Session {
+            refresh_token: persisted.refresh_token,
+            user_id: persisted.user_id,
+            email: persisted.email,
+            session_id: persisted.session_id,
+            ..Session::default()
+        });
+    }
+    // Let the background refresh task retry if the refresh below fails transiently
+    session_store.session_changed.notify_one();
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let session_store = app.state::<SessionStore>();
+        match refresh_tokens(&session_store).await {
+            Ok(RefreshOutcome::Rejected) => println!("Stored session was rejected; sign-in required"),
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to restore session: {}", e),
+        }
+        drop(restoring);
+    });
+}
+
+/// Build a session from freshly issued tokens. Both tokens are verified against the
+/// user pool's signing keys, and the user id, email and expiry come from their claims.
+pub(crate) async fn session_from_tokens(
+    access_token: &str,
+    refresh_token: &str,
+    id_token: &str,
+) -> Result<Session, String> {
+    let access = verify_access_token(access_token).await?;
+    let id = verify_id_token(id_token).await?;
+
+    if access.sub != id.sub {
+        return Err("Invalid token: access and ID tokens belong to different users".to_string());
+    }
+
+    Ok(Session {
+        access_token: access_token.to_string(),
+        refresh_token: refresh_token.to_string(),
+        id_token: id_token.to_string(),
+        user_id: id.sub,
+        email: id.email,
+        expires_at: access.exp,
+        session_id: String::new(),
+    })
+}
+
 /// Tauri command to get WebSocket URL for realtime connections
 #[command]
 pub fn get_websocket_url() -> String {
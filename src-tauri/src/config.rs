@@ -17,6 +17,11 @@ pub fn s3_bucket() -> String {
     env::var("S3_BUCKET").expect("S3_BUCKET must be set")
 }
 
+/// Optional S3-compatible endpoint (e.g. a local MinIO); unset means AWS S3
+pub fn s3_endpoint() -> Option<String> {
+    env::var("S3_ENDPOINT").ok().filter(|url| !url.trim().is_empty())
+}
+
 pub fn cloudfront_url() -> String {
     env::var("CLOUDFRONT_URL").expect("CLOUDFRONT_URL must be set")
 }
@@ -30,6 +35,42 @@ pub fn cognito_client_id() -> String {
     env::var("COGNITO_CLIENT_ID").expect("COGNITO_CLIENT_ID must be set")
 }
 
+/// Hosted UI domain, e.g. `myapp.auth.us-east-2.amazoncognito.com`
+pub fn cognito_domain() -> String {
+    let domain = env::var("COGNITO_DOMAIN").expect("COGNITO_DOMAIN must be set");
+    domain
+        .trim()
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// Friend requests
+/// Days a friend request stays pending before it expires
+pub fn friend_request_expiry_days() -> i64 {
+    env::var("FRIEND_REQUEST_EXPIRY_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30)
+}
+
+/// Days a declined sender must wait before requesting the same user again
+pub fn friend_request_cooldown_days() -> i64 {
+    env::var("FRIEND_REQUEST_COOLDOWN_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(7)
+}
+
+// Account API
+/// Base URL of the server-side account API, which owns MFA recovery codes and
+/// revokes sessions on other devices. Its contract is in `docs/account-api.md`.
+pub fn account_api_url() -> Result<String, String> {
+    env::var("ACCOUNT_API_URL")
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .map_err(|_| "ACCOUNT_API_URL must be set".to_string())
+}
+
 // WebSocket
 pub fn websocket_url() -> String {
     env::var("WEBSOCKET_URL").expect("WEBSOCKET_URL must be set")
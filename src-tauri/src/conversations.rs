@@ -1,5 +1,7 @@
-use crate::auth::SessionStore;
+use crate::auth::{get_user_id_from_store, SessionStore};
 use crate::db::get_pool;
+use crate::privacy::can_start_dm;
+use crate::profile::touch_last_seen;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use tauri::{command, State};
@@ -46,28 +48,6 @@ pub struct MessageResult {
     pub error: Option<String>,
 }
 
-// ============================================
-// HELPER FUNCTIONS
-// ============================================
-
-fn get_user_id_from_store(session_store: &SessionStore) -> Result<String, String> {
-    let store = session_store
-        .session
-        .lock()
-        .map_err(|e| format!("Failed to lock session: {}", e))?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Err("Session expired. Please sign in again.".to_string())
-            } else {
-                Ok(session.user_id.clone())
-            }
-        }
-        None => Err("Not authenticated. Please sign in.".to_string()),
-    }
-}
-
 // ============================================
 // CONVERSATION COMMANDS
 // ============================================
@@ -78,7 +58,7 @@ pub async fn get_or_create_dm_conversation(
     other_user_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<ConversationResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Validate other_user_id
@@ -133,6 +113,15 @@ pub async fn get_or_create_dm_conversation(
         });
     }
 
+    // Existing DMs stay reachable, but starting a new one needs the other user's permission
+    if !can_start_dm(&other_user_id, &user_id).await? {
+        return Ok(ConversationResult {
+            success: false,
+            conversation_id: None,
+            error: Some("This user is not accepting direct messages".to_string()),
+        });
+    }
+
     // Create new conversation with a unique key to prevent duplicates
     // Try to insert - if duplicate key, fetch the existing conversation
     let insert_result: Result<(String,), _> = sqlx::query_as(
@@ -196,7 +185,7 @@ pub async fn get_or_create_dm_conversation(
 pub async fn get_conversations(
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<ConversationWithDetails>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Complex query to get conversations with all details
@@ -222,13 +211,17 @@ pub async fn get_conversations(
             (SELECT p.nickname FROM profiles p 
              JOIN conversation_participants cp2 ON p.user_id = cp2.user_id
              WHERE cp2.conversation_id = c.id AND cp2.user_id != $1 LIMIT 1) as other_user_nickname,
-            -- Get last message
+            -- Get last message (skipping blocked senders in groups)
             (SELECT m.content FROM messages m 
              WHERE m.conversation_id = c.id 
+             AND (c.type = 'direct' OR NOT EXISTS (
+                 SELECT 1 FROM blocked_users b WHERE b.blocker_id = $1 AND b.blocked_id = m.sender_id))
              ORDER BY m.timestamp DESC LIMIT 1) as last_message,
             -- Get last message time
             (SELECT m.timestamp FROM messages m 
              WHERE m.conversation_id = c.id 
+             AND (c.type = 'direct' OR NOT EXISTS (
+                 SELECT 1 FROM blocked_users b WHERE b.blocker_id = $1 AND b.blocked_id = m.sender_id))
              ORDER BY m.timestamp DESC LIMIT 1) as last_message_time,
             -- Check for unread messages
             COALESCE(
@@ -275,7 +268,7 @@ pub async fn get_messages(
     conversation_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<Message>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     if uuid::Uuid::parse_str(&conversation_id).is_err() {
@@ -296,13 +289,18 @@ pub async fn get_messages(
         return Err("You are not a participant in this conversation".to_string());
     }
 
+    // Messages from users the viewer has blocked are hidden in group conversations
     let messages: Vec<Message> = sqlx::query_as(
-        "SELECT id::text, conversation_id::text, sender_id, content, timestamp 
-         FROM messages 
-         WHERE conversation_id = $1::uuid 
-         ORDER BY timestamp ASC"
+        "SELECT m.id::text, m.conversation_id::text, m.sender_id, m.content, m.timestamp 
+         FROM messages m
+         JOIN conversations c ON c.id = m.conversation_id
+         WHERE m.conversation_id = $1::uuid 
+         AND (c.type = 'direct' OR NOT EXISTS (
+             SELECT 1 FROM blocked_users b WHERE b.blocker_id = $2 AND b.blocked_id = m.sender_id))
+         ORDER BY m.timestamp ASC"
     )
     .bind(&conversation_id)
+    .bind(&user_id)
     .fetch_all(pool.as_ref())
     .await
     .map_err(|e| format!("Database error: {}", e))?;
@@ -317,7 +315,7 @@ pub async fn send_message(
     content: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<MessageResult, String> {
-    let sender_id = get_user_id_from_store(&session_store)?;
+    let sender_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Validation
@@ -359,6 +357,26 @@ pub async fn send_message(
         });
     }
 
+    // No new messages in a DM once either side has blocked the other
+    let (blocked,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (
+            SELECT 1 FROM conversations c
+            JOIN conversation_participants cp ON cp.conversation_id = c.id AND cp.user_id != $2
+            WHERE c.id = $1::uuid AND c.type = 'direct' AND is_blocked_between(cp.user_id, $2))"
+    )
+    .bind(&conversation_id)
+    .bind(&sender_id)
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if blocked {
+        return Ok(MessageResult {
+            success: false,
+            error: Some("You can no longer message this user".to_string()),
+        });
+    }
+
     let timestamp = chrono::Utc::now().timestamp_millis();
 
     let result = sqlx::query(
@@ -376,6 +394,7 @@ pub async fn send_message(
         .bind(&conversation_id)
         .execute(pool.as_ref())
         .await;
+    let _ = touch_last_seen(&sender_id).await;
 
     match result {
         Ok(_) => Ok(MessageResult {
@@ -395,7 +414,7 @@ pub async fn mark_conversation_read(
     conversation_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<MessageResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     if uuid::Uuid::parse_str(&conversation_id).is_err() {
@@ -412,6 +431,7 @@ pub async fn mark_conversation_read(
     .bind(&user_id)
     .execute(pool.as_ref())
     .await;
+    let _ = touch_last_seen(&user_id).await;
 
     match result {
         Ok(_) => Ok(MessageResult {
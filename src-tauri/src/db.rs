@@ -18,6 +18,9 @@ pub async fn init_db() -> Result<(), sqlx::Error> {
     // Test the connection
     sqlx::query("SELECT 1").execute(&pool).await?;
 
+    // Apply pending schema migrations from src-tauri/migrations
+    sqlx::migrate!().run(&pool).await?;
+
     DB_POOL
         .set(Arc::new(pool))
         .map_err(|_| sqlx::Error::Configuration("Pool already initialized".into()))?;
@@ -0,0 +1,386 @@
+use crate::auth::SessionStore;
+use crate::invites::{redeem_invite_link, InviteRedemption};
+use crate::oauth::{complete_oauth_sign_in, fail_oauth_sign_in};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use url::Url;
+use uuid::Uuid;
+
+/// URL scheme registered for the app in `tauri.conf.json`
+pub const DEEP_LINK_SCHEME: &str = "cryptex";
+
+/// Longest link accepted; anything bigger is not one we generated
+const MAX_LINK_LENGTH: usize = 2048;
+
+/// Longest user id or invite token accepted in a path segment
+const MAX_SEGMENT_LENGTH: usize = 256;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// A validated `cryptex://` link. Auth callbacks and invites are handled in Rust;
+/// the rest are emitted to the frontend as `deep-link`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "route", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// `cryptex://auth/callback?code=...&state=...`
+    OauthCallback { code: String, state: Option<String> },
+    /// `cryptex://auth/callback?error=...&error_description=...`
+    OauthError {
+        error: String,
+        description: Option<String>,
+    },
+    /// `cryptex://conversation/<conversation id>`
+    Conversation { conversation_id: String },
+    /// `cryptex://user/<user id>`
+    UserProfile { user_id: String },
+    /// `cryptex://add-friend/<invite token>`
+    FriendInvite { token: String },
+}
+
+/// A link that failed validation, emitted as `deep-link-rejected`
+#[derive(Serialize, Clone)]
+pub struct RejectedDeepLink {
+    pub url: String,
+    pub reason: String,
+}
+
+/// Links received before the frontend registered its listeners (e.g. at cold start)
+#[derive(Default)]
+pub struct DeepLinkQueue {
+    state: Mutex<QueueState>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    listening: bool,
+    pending: Vec<String>,
+}
+
+// ============================================
+// PARSING
+// ============================================
+
+/// `cryptex://add-friend/<token>` for an invite token
+pub(crate) fn friend_invite_url(token: &str) -> String {
+    format!("{}://add-friend/{}", DEEP_LINK_SCHEME, token)
+}
+
+/// `cryptex://auth/callback`, where the hosted UI sends users after signing in
+pub(crate) fn oauth_redirect_url() -> String {
+    format!("{}://auth/callback", DEEP_LINK_SCHEME)
+}
+
+/// Ids and tokens are plain ASCII words; anything else is rejected before it reaches a query
+fn is_valid_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment.len() <= MAX_SEGMENT_LENGTH
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// The single path segment after the host, e.g. `abc` in `cryptex://user/abc`
+fn single_segment(url: &Url) -> Result<String, String> {
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+
+    match segments.as_slice() {
+        [segment] if is_valid_segment(segment) => Ok(segment.to_string()),
+        [_] => Err("Link contains an invalid id".to_string()),
+        [] => Err("Link is missing an id".to_string()),
+        _ => Err("Link has too many path segments".to_string()),
+    }
+}
+
+fn query_param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_oauth_callback(url: &Url) -> Result<DeepLink, String> {
+    if url.path().trim_end_matches('/') != "/callback" {
+        return Err("Unknown auth link".to_string());
+    }
+
+    if let Some(error) = query_param(url, "error") {
+        return Ok(DeepLink::OauthError {
+            error,
+            description: query_param(url, "error_description"),
+        });
+    }
+
+    match query_param(url, "code") {
+        Some(code) => Ok(DeepLink::OauthCallback {
+            code,
+            state: query_param(url, "state"),
+        }),
+        None => Err("Auth callback is missing its authorization code".to_string()),
+    }
+}
+
+/// Parse and validate a `cryptex://` link into a typed route
+pub fn parse_deep_link(link: &str) -> Result<DeepLink, String> {
+    let link = link.trim();
+    if link.len() > MAX_LINK_LENGTH {
+        return Err("Link is too long".to_string());
+    }
+
+    let url = Url::parse(link).map_err(|e| format!("Malformed link: {}", e))?;
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return Err(format!("Unsupported link scheme: {}", url.scheme()));
+    }
+
+    match url.host_str() {
+        Some("auth") => parse_oauth_callback(&url),
+        Some("conversation") => {
+            let conversation_id = single_segment(&url)?;
+            Uuid::parse_str(&conversation_id)
+                .map_err(|_| "Link contains an invalid conversation id".to_string())?;
+            Ok(DeepLink::Conversation { conversation_id })
+        }
+        Some("user") => {
+            let user_id = single_segment(&url)?;
+            Uuid::parse_str(&user_id).map_err(|_| "Link contains an invalid user id".to_string())?;
+            Ok(DeepLink::UserProfile { user_id })
+        }
+        Some("add-friend") => Ok(DeepLink::FriendInvite {
+            token: single_segment(&url)?,
+        }),
+        Some(route) => Err(format!("Unknown link route: {}", route)),
+        None => Err("Link has no route".to_string()),
+    }
+}
+
+// ============================================
+// DISPATCH
+// ============================================
+
+/// Route a validated link to the frontend. OAuth callbacks and invites are handled here
+/// and only the outcome is emitted.
+fn dispatch(app: &AppHandle, url: &str) {
+    match parse_deep_link(url) {
+        Ok(DeepLink::OauthCallback { code, state }) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = complete_oauth_sign_in(&app, &code, state.as_deref())
+                    .await
+                    .unwrap_or_else(|e| fail_oauth_sign_in(&app, &e, None));
+                let _ = app.emit("oauth-sign-in", result);
+            });
+        }
+        Ok(DeepLink::OauthError { error, description }) => {
+            let result = fail_oauth_sign_in(app, &error, description.as_deref());
+            let _ = app.emit("oauth-sign-in", result);
+        }
+        Ok(DeepLink::FriendInvite { token }) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let session_store = app.state::<SessionStore>();
+                let redemption = redeem_invite_link(&session_store, &token)
+                    .await
+                    .unwrap_or_else(|e| InviteRedemption::failed(&e));
+                let _ = app.emit("friend-invite-redeemed", redemption);
+            });
+        }
+        Ok(link) => {
+            let _ = app.emit("deep-link", link);
+        }
+        Err(reason) => {
+            eprintln!("Rejected deep link {}: {}", url, reason);
+            let _ = app.emit(
+                "deep-link-rejected",
+                RejectedDeepLink {
+                    url: url.to_string(),
+                    reason,
+                },
+            );
+        }
+    }
+}
+
+/// Entry point for every incoming link (cold start, single-instance relaunch, OS open events).
+/// Links are held until the frontend calls `flush_pending_deep_links`.
+pub fn handle_deep_link(app: &AppHandle, url: &str) {
+    {
+        let queue = app.state::<DeepLinkQueue>();
+        let mut state = match queue.state.lock() {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Deep link queue poisoned: {}", e);
+                return;
+            }
+        };
+        if !state.listening {
+            state.pending.push(url.to_string());
+            return;
+        }
+    }
+
+    dispatch(app, url);
+}
+
+// ============================================
+// DEEP LINK COMMANDS
+// ============================================
+
+/// Called once the frontend is listening for deep-link events; delivers anything queued so far
+#[command]
+pub fn flush_pending_deep_links(
+    app: AppHandle,
+    queue: State<'_, DeepLinkQueue>,
+) -> Result<(), String> {
+    let pending = {
+        let mut state = queue.state.lock().map_err(|e| e.to_string())?;
+        state.listening = true;
+        std::mem::take(&mut state.pending)
+    };
+
+    for url in pending {
+        dispatch(&app, &url);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0b7c5e2e-4d1a-4f3e-9c55-2a6f0d9e8b13";
+
+    #[test]
+    fn parses_oauth_callback() {
+        assert_eq!(
+            parse_deep_link("cryptex://auth/callback?code=abc123&state=xyz"),
+            Ok(DeepLink::OauthCallback {
+                code: "abc123".to_string(),
+                state: Some("xyz".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_deep_link("cryptex://auth/callback/?code=abc123"),
+            Ok(DeepLink::OauthCallback {
+                code: "abc123".to_string(),
+                state: None,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_oauth_error() {
+        assert_eq!(
+            parse_deep_link("cryptex://auth/callback?error=access_denied&error_description=User+cancelled"),
+            Ok(DeepLink::OauthError {
+                error: "access_denied".to_string(),
+                description: Some("User cancelled".to_string()),
+            })
+        );
+        // An error wins over a code sent alongside it
+        assert_eq!(
+            parse_deep_link("cryptex://auth/callback?code=abc&error=server_error"),
+            Ok(DeepLink::OauthError {
+                error: "server_error".to_string(),
+                description: None,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_auth_links() {
+        assert!(parse_deep_link("cryptex://auth/callback").is_err());
+        assert!(parse_deep_link("cryptex://auth/callback?code=").is_err());
+        assert!(parse_deep_link("cryptex://auth/logout?code=abc").is_err());
+    }
+
+    #[test]
+    fn parses_conversation() {
+        assert_eq!(
+            parse_deep_link(&format!("cryptex://conversation/{}", ID)),
+            Ok(DeepLink::Conversation {
+                conversation_id: ID.to_string(),
+            })
+        );
+        assert!(parse_deep_link("cryptex://conversation/not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn parses_user_profile() {
+        assert_eq!(
+            parse_deep_link(&format!("cryptex://user/{}", ID)),
+            Ok(DeepLink::UserProfile {
+                user_id: ID.to_string(),
+            })
+        );
+        assert!(parse_deep_link("cryptex://user/alice").is_err());
+        assert!(parse_deep_link("cryptex://user/1234").is_err());
+    }
+
+    #[test]
+    fn parses_friend_invite() {
+        let token = "0b7c5e2e4d1a4f3e9c552a6f0d9e8b13";
+        assert_eq!(
+            parse_deep_link(&friend_invite_url(token)),
+            Ok(DeepLink::FriendInvite {
+                token: token.to_string(),
+            })
+        );
+        assert!(parse_deep_link("cryptex://add-friend/bad%20token").is_err());
+        assert!(parse_deep_link("cryptex://add-friend/<script>").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_scheme() {
+        assert_eq!(
+            parse_deep_link(&format!("https://user/{}", ID)),
+            Err("Unsupported link scheme: https".to_string())
+        );
+        assert!(parse_deep_link("not a link").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_or_missing_route() {
+        assert_eq!(
+            parse_deep_link(&format!("cryptex://settings/{}", ID)),
+            Err("Unknown link route: settings".to_string())
+        );
+        assert!(parse_deep_link("cryptex:user").is_err());
+    }
+
+    #[test]
+    fn rejects_extra_or_empty_segments() {
+        assert_eq!(
+            parse_deep_link(&format!("cryptex://user/{}/extra", ID)),
+            Err("Link has too many path segments".to_string())
+        );
+        assert_eq!(
+            parse_deep_link("cryptex://user/"),
+            Err("Link is missing an id".to_string())
+        );
+        assert_eq!(
+            parse_deep_link("cryptex://add-friend"),
+            Err("Link is missing an id".to_string())
+        );
+        // Empty segments are ignored rather than counted
+        assert!(parse_deep_link(&format!("cryptex://user/{}/", ID)).is_ok());
+    }
+
+    #[test]
+    fn rejects_over_long_input() {
+        let segment = "a".repeat(MAX_SEGMENT_LENGTH + 1);
+        assert_eq!(
+            parse_deep_link(&format!("cryptex://add-friend/{}", segment)),
+            Err("Link contains an invalid id".to_string())
+        );
+        assert!(parse_deep_link(&format!("cryptex://add-friend/{}", "a".repeat(MAX_SEGMENT_LENGTH))).is_ok());
+
+        let link = format!("cryptex://auth/callback?code={}", "a".repeat(MAX_LINK_LENGTH));
+        assert_eq!(parse_deep_link(&link), Err("Link is too long".to_string()));
+    }
+}
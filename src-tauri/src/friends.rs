@@ -1,72 +1,227 @@
-use crate::auth::SessionStore;
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::config::{friend_request_cooldown_days, friend_request_expiry_days};
 use crate::db::get_pool;
+use crate::privacy::can_send_friend_request;
+use crate::profile::normalize_optional;
 use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor, Postgres, Transaction};
 use tauri::{command, State};
+use uuid::Uuid;
+
+/// Sort orders accepted by `get_friends`
+pub const FRIEND_SORT_OPTIONS: [&str; 4] = ["name", "recent", "favorites", "unread"];
+
+/// Limits for friend organization fields
+const MAX_CATEGORIES: i64 = 50;
+const MAX_CATEGORY_NAME_LENGTH: usize = 32;
+const MAX_NOTE_LENGTH: usize = 500;
+const MAX_LOCAL_NICKNAME_LENGTH: usize = 32;
+
+/// Longest message that can accompany a friend request
+const MAX_REQUEST_MESSAGE_LENGTH: usize = 200;
+
+/// Friend request history page size bounds
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+const MAX_HISTORY_LIMIT: i64 = 100;
 
 // ============================================
 // TYPES
 // ============================================
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, FromRow)]
 pub struct FriendWithProfile {
     pub friend_id: String,
     pub username: String,
     pub nickname: String,
     pub created_at: String,
+    pub is_favorite: bool,
+    pub category_id: Option<String>,
+    pub category_name: Option<String>,
+    /// Private note only the current user can see
+    pub note: Option<String>,
+    /// Name the current user gave this friend, shown in place of their nickname
+    pub local_nickname: Option<String>,
+    pub avatar_url: Option<String>,
+    /// Presence fields are None when the friend hides their status from the current user
+    pub status: Option<String>,
+    pub custom_status: Option<String>,
+    pub last_seen_at: Option<String>,
+    pub is_online: bool,
+    /// Existing DM with this friend, if any
+    pub dm_conversation_id: Option<String>,
+    pub unread_count: i64,
 }
 
+/// Friends split by presence for the friends list
 #[derive(Serialize, Deserialize, Debug)]
+pub struct FriendsByPresence {
+    pub online: Vec<FriendWithProfile>,
+    pub offline: Vec<FriendWithProfile>,
+}
+
+/// A user-defined group of friends
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct FriendCategory {
+    pub id: String,
+    pub name: String,
+    pub friend_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, FromRow)]
 pub struct FriendRequestWithProfile {
     pub id: String,
     pub from_user_id: String,
     pub to_user_id: String,
     pub status: String,
     pub created_at: String,
+    pub message: Option<String>,
+    pub expires_at: Option<String>,
+    /// When the request was accepted, declined, cancelled or expired
+    pub responded_at: Option<String>,
     pub from_username: Option<String>,
     pub from_nickname: Option<String>,
     pub to_username: Option<String>,
     pub to_nickname: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct BlockedUser {
+    pub user_id: String,
+    pub username: Option<String>,
+    pub nickname: Option<String>,
+    pub avatar_url: Option<String>,
+    pub blocked_at: String,
+}
+
+/// Minimal profile of a mutual friend shown on a suggestion
+#[derive(Serialize, Deserialize, Debug, Clone, FromRow)]
+pub struct MutualFriendPreview {
+    pub user_id: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FriendSuggestion {
+    pub user_id: String,
+    pub username: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub mutual_friend_count: i64,
+    pub shared_group_count: i64,
+    pub mutual_friends: Vec<MutualFriendPreview>,
+}
+
+/// Raw suggestion row before mutual friend previews are attached
+#[derive(FromRow)]
+struct SuggestionRow {
+    user_id: String,
+    username: String,
+    nickname: String,
+    avatar_url: Option<String>,
+    mutual_friend_count: i64,
+    shared_group_count: i64,
+    mutual_friend_ids: Vec<String>,
+}
+
 #[derive(Serialize)]
 pub struct FriendsResult {
     pub success: bool,
     pub error: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct FriendCategoryResult {
+    pub success: bool,
+    pub category_id: Option<String>,
+    pub error: Option<String>,
+}
+
 // ============================================
 // HELPER FUNCTIONS
 // ============================================
 
-fn get_user_id_from_store(session_store: &SessionStore) -> Result<String, String> {
-    let store = session_store
-        .session
-        .lock()
-        .map_err(|e| format!("Failed to lock session: {}", e))?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Err("Session expired. Please sign in again.".to_string())
-            } else {
-                Ok(session.user_id.clone())
-            }
-        }
-        None => Err("Not authenticated. Please sign in.".to_string()),
-    }
+/// Serialize every friend-lifecycle change between two users for the rest of the
+/// transaction. The key is order-independent, so A->B and B->A contend on the same lock.
+pub(crate) async fn lock_friend_pair(
+    tx: &mut Transaction<'_, Postgres>,
+    user_a: &str,
+    user_b: &str,
+) -> Result<(), String> {
+    let (first, second) = if user_a < user_b {
+        (user_a, user_b)
+    } else {
+        (user_b, user_a)
+    };
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("friends:{}:{}", first, second))
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+/// Mark a user's overdue pending requests, sent or received, as expired.
+/// Expiry is applied lazily whenever that user's requests are read or changed.
+pub(crate) async fn expire_friend_requests<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE friend_requests SET status = 'expired', responded_at = expires_at
+         WHERE status = 'pending' AND expires_at <= NOW()
+           AND (from_user_id = $1 OR to_user_id = $1)"
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+/// Insert both directions of a friendship, ignoring rows that already exist
+pub(crate) async fn create_friendship(
+    tx: &mut Transaction<'_, Postgres>,
+    user_a: &str,
+    user_b: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO friends (user_id, friend_id) VALUES ($1, $2), ($2, $1)
+         ON CONFLICT (user_id, friend_id) DO NOTHING"
+    )
+    .bind(user_a)
+    .bind(user_b)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
 }
 
 // ============================================
 // FRIEND REQUEST COMMANDS
 // ============================================
 
-/// Send a friend request to another user by their username
+/// Send a friend request to another user by their username, with an optional short message
 #[command]
 pub async fn send_friend_request(
     to_username: String,
+    message: Option<String>,
     session_store: State<'_, SessionStore>,
 ) -> Result<FriendsResult, String> {
-    let from_user_id = get_user_id_from_store(&session_store)?;
+    let from_user_id = get_user_id_from_store(&session_store).await?;
+    send_friend_request_from(from_user_id, to_username, message).await
+}
+
+async fn send_friend_request_from(
+    from_user_id: String,
+    to_username: String,
+    message: Option<String>,
+) -> Result<FriendsResult, String> {
     let pool = get_pool();
 
     if to_username.trim().is_empty() {
@@ -76,6 +231,20 @@ pub async fn send_friend_request(
         });
     }
 
+    let message = normalize_optional(message);
+    if message
+        .as_ref()
+        .is_some_and(|m| m.chars().count() > MAX_REQUEST_MESSAGE_LENGTH)
+    {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some(format!(
+                "Messages must be at most {} characters",
+                MAX_REQUEST_MESSAGE_LENGTH
+            )),
+        });
+    }
+
     // Look up user by username
     let target: Option<(String,)> = sqlx::query_as(
         "SELECT user_id FROM profiles WHERE username = $1"
@@ -103,13 +272,22 @@ pub async fn send_friend_request(
         });
     }
 
+    // Everything below runs under the pair lock, so concurrent sends can't both insert
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    lock_friend_pair(&mut tx, &from_user_id, &to_user_id).await?;
+    expire_friend_requests(&mut *tx, &from_user_id).await?;
+
     // Check if already friends
     let existing_friend: Option<(String,)> = sqlx::query_as(
         "SELECT id::text FROM friends WHERE user_id = $1 AND friend_id = $2"
     )
     .bind(&from_user_id)
     .bind(&to_user_id)
-    .fetch_optional(pool.as_ref())
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
@@ -120,44 +298,103 @@ pub async fn send_friend_request(
         });
     }
 
-    // Check for existing pending request (in either direction)
-    let existing_request: Option<(String,)> = sqlx::query_as(
-        "SELECT id::text FROM friend_requests 
-         WHERE status = 'pending' 
-         AND ((from_user_id = $1 AND to_user_id = $2) OR (from_user_id = $2 AND to_user_id = $1))"
+    // If they already asked us, sending back counts as accepting
+    let crossing_request: Option<(String,)> = sqlx::query_as(
+        "SELECT id::text FROM friend_requests
+         WHERE from_user_id = $1 AND to_user_id = $2 AND status = 'pending'"
+    )
+    .bind(&to_user_id)
+    .bind(&from_user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if let Some((request_id,)) = crossing_request {
+        sqlx::query(
+            "UPDATE friend_requests SET status = 'accepted', responded_at = NOW() WHERE id = $1::uuid"
+        )
+            .bind(&request_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        create_friendship(&mut tx, &from_user_id, &to_user_id).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        return Ok(FriendsResult {
+            success: true,
+            error: None,
+        });
+    }
+
+    // A declined sender has to wait out the cooldown before asking again
+    let recently_declined: Option<(i32,)> = sqlx::query_as(
+        "SELECT 1 FROM friend_requests
+         WHERE from_user_id = $1 AND to_user_id = $2 AND status = 'declined'
+           AND responded_at > NOW() - $3 * INTERVAL '1 day'
+         LIMIT 1"
     )
     .bind(&from_user_id)
     .bind(&to_user_id)
-    .fetch_optional(pool.as_ref())
+    .bind(friend_request_cooldown_days())
+    .fetch_optional(&mut *tx)
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
-    if existing_request.is_some() {
+    if recently_declined.is_some() {
         return Ok(FriendsResult {
             success: false,
-            error: Some("A friend request already exists between you and this user".to_string()),
+            error: Some("You can't send this user another friend request yet".to_string()),
         });
     }
 
-    // Send the friend request
+    // Respect the recipient's friend request privacy setting
+    if !can_send_friend_request(&mut *tx, &to_user_id, &from_user_id).await? {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("This user is not accepting friend requests".to_string()),
+        });
+    }
+
+    // Send the friend request; the pending-pair unique index backs up the lock
     let result = sqlx::query(
-        "INSERT INTO friend_requests (from_user_id, to_user_id, status) VALUES ($1, $2, 'pending')"
+        "INSERT INTO friend_requests (from_user_id, to_user_id, status, message, expires_at)
+         VALUES ($1, $2, 'pending', $3, NOW() + $4 * INTERVAL '1 day')"
     )
     .bind(&from_user_id)
     .bind(&to_user_id)
-    .execute(pool.as_ref())
+    .bind(&message)
+    .bind(friend_request_expiry_days())
+    .execute(&mut *tx)
     .await;
 
     match result {
-        Ok(_) => Ok(FriendsResult {
-            success: true,
-            error: None,
-        }),
-        Err(e) => Ok(FriendsResult {
-            success: false,
-            error: Some(format!("Failed to send friend request: {}", e)),
-        }),
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(FriendsResult {
+                success: false,
+                error: Some("A friend request already exists between you and this user".to_string()),
+            });
+        }
+        Err(e) => {
+            return Ok(FriendsResult {
+                success: false,
+                error: Some(format!("Failed to send friend request: {}", e)),
+            });
+        }
     }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(FriendsResult {
+        success: true,
+        error: None,
+    })
 }
 
 /// Get all pending friend requests received by the current user
@@ -165,13 +402,18 @@ pub async fn send_friend_request(
 pub async fn get_incoming_friend_requests(
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<FriendRequestWithProfile>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
     // Join with profiles to get sender info
-    let rows: Vec<(String, String, String, String, String, Option<String>, Option<String>)> = sqlx::query_as(
-        "SELECT fr.id::text, fr.from_user_id, fr.to_user_id, fr.status, fr.created_at::text,
-                p.username, p.nickname
+    let requests: Vec<FriendRequestWithProfile> = sqlx::query_as(
+        "SELECT fr.id::text AS id, fr.from_user_id, fr.to_user_id, fr.status,
+                fr.created_at::text AS created_at, fr.message, fr.expires_at::text AS expires_at,
+                fr.responded_at::text AS responded_at,
+                p.username AS from_username, p.nickname AS from_nickname,
+                NULL::text AS to_username, NULL::text AS to_nickname
          FROM friend_requests fr
          LEFT JOIN profiles p ON fr.from_user_id = p.user_id
          WHERE fr.to_user_id = $1 AND fr.status = 'pending'
@@ -182,24 +424,7 @@ pub async fn get_incoming_friend_requests(
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
-    let results: Vec<FriendRequestWithProfile> = rows
-        .into_iter()
-        .map(|(id, from_user_id, to_user_id, status, created_at, username, nickname)| {
-            FriendRequestWithProfile {
-                id,
-                from_user_id,
-                to_user_id,
-                status,
-                created_at,
-                from_username: username,
-                from_nickname: nickname,
-                to_username: None,
-                to_nickname: None,
-            }
-        })
-        .collect();
-
-    Ok(results)
+    Ok(requests)
 }
 
 /// Get all pending friend requests sent by the current user
@@ -207,13 +432,18 @@ pub async fn get_incoming_friend_requests(
 pub async fn get_outgoing_friend_requests(
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<FriendRequestWithProfile>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
     // Join with profiles to get recipient info
-    let rows: Vec<(String, String, String, String, String, Option<String>, Option<String>)> = sqlx::query_as(
-        "SELECT fr.id::text, fr.from_user_id, fr.to_user_id, fr.status, fr.created_at::text,
-                p.username, p.nickname
+    let requests: Vec<FriendRequestWithProfile> = sqlx::query_as(
+        "SELECT fr.id::text AS id, fr.from_user_id, fr.to_user_id, fr.status,
+                fr.created_at::text AS created_at, fr.message, fr.expires_at::text AS expires_at,
+                fr.responded_at::text AS responded_at,
+                NULL::text AS from_username, NULL::text AS from_nickname,
+                p.username AS to_username, p.nickname AS to_nickname
          FROM friend_requests fr
          LEFT JOIN profiles p ON fr.to_user_id = p.user_id
          WHERE fr.from_user_id = $1 AND fr.status = 'pending'
@@ -224,24 +454,46 @@ pub async fn get_outgoing_friend_requests(
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
-    let results: Vec<FriendRequestWithProfile> = rows
-        .into_iter()
-        .map(|(id, from_user_id, to_user_id, status, created_at, username, nickname)| {
-            FriendRequestWithProfile {
-                id,
-                from_user_id,
-                to_user_id,
-                status,
-                created_at,
-                from_username: None,
-                from_nickname: None,
-                to_username: username,
-                to_nickname: nickname,
-            }
-        })
-        .collect();
+    Ok(requests)
+}
 
-    Ok(results)
+/// Get the current user's finished friend requests in both directions
+/// (accepted, declined, cancelled or expired), newest first
+#[command]
+pub async fn get_friend_request_history(
+    limit: Option<i64>,
+    offset: Option<i64>,
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<FriendRequestWithProfile>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+    let offset = offset.unwrap_or(0).max(0);
+
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
+    let requests: Vec<FriendRequestWithProfile> = sqlx::query_as(
+        "SELECT fr.id::text AS id, fr.from_user_id, fr.to_user_id, fr.status,
+                fr.created_at::text AS created_at, fr.message, fr.expires_at::text AS expires_at,
+                fr.responded_at::text AS responded_at,
+                fp.username AS from_username, fp.nickname AS from_nickname,
+                tp.username AS to_username, tp.nickname AS to_nickname
+         FROM friend_requests fr
+         LEFT JOIN profiles fp ON fr.from_user_id = fp.user_id
+         LEFT JOIN profiles tp ON fr.to_user_id = tp.user_id
+         WHERE (fr.from_user_id = $1 OR fr.to_user_id = $1) AND fr.status <> 'pending'
+         ORDER BY COALESCE(fr.responded_at, fr.created_at) DESC
+         LIMIT $2 OFFSET $3"
+    )
+    .bind(&user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(requests)
 }
 
 /// Accept a friend request
@@ -250,12 +502,18 @@ pub async fn accept_friend_request(
     request_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<FriendsResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
+    accept_friend_request_as(user_id, request_id).await
+}
+
+async fn accept_friend_request_as(user_id: String, request_id: String) -> Result<FriendsResult, String> {
     let pool = get_pool();
 
-    // Verify this request is for the current user and get the sender
-    let request: Option<(String, String)> = sqlx::query_as(
-        "SELECT from_user_id, to_user_id FROM friend_requests 
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
+    // Find the sender first so the pair lock can be taken before touching the request
+    let request: Option<(String,)> = sqlx::query_as(
+        "SELECT from_user_id FROM friend_requests 
          WHERE id = $1::uuid AND to_user_id = $2 AND status = 'pending'"
     )
     .bind(&request_id)
@@ -264,8 +522,8 @@ pub async fn accept_friend_request(
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
-    let (from_user_id, to_user_id) = match request {
-        Some(r) => r,
+    let from_user_id = match request {
+        Some((id,)) => id,
         None => {
             return Ok(FriendsResult {
                 success: false,
@@ -274,20 +532,37 @@ pub async fn accept_friend_request(
         }
     };
 
-    // Update request status
-    sqlx::query("UPDATE friend_requests SET status = 'accepted' WHERE id = $1::uuid")
-        .bind(&request_id)
-        .execute(pool.as_ref())
+    let mut tx = pool
+        .begin()
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    lock_friend_pair(&mut tx, &from_user_id, &user_id).await?;
+
+    // Re-check under the lock: the request may have been cancelled or accepted meanwhile
+    let updated = sqlx::query(
+        "UPDATE friend_requests SET status = 'accepted', responded_at = NOW()
+         WHERE id = $1::uuid AND to_user_id = $2 AND status = 'pending'"
+    )
+    .bind(&request_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if updated.rows_affected() == 0 {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Friend request not found".to_string()),
+        });
+    }
 
     // Create bidirectional friendship
-    sqlx::query("INSERT INTO friends (user_id, friend_id) VALUES ($1, $2), ($2, $1)")
-        .bind(&from_user_id)
-        .bind(&to_user_id)
-        .execute(pool.as_ref())
+    create_friendship(&mut tx, &from_user_id, &user_id).await?;
+
+    tx.commit()
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
     Ok(FriendsResult {
         success: true,
@@ -301,11 +576,14 @@ pub async fn decline_friend_request(
     request_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<FriendsResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
     let result = sqlx::query(
-        "UPDATE friend_requests SET status = 'declined' WHERE id = $1::uuid AND to_user_id = $2"
+        "UPDATE friend_requests SET status = 'declined', responded_at = NOW()
+         WHERE id = $1::uuid AND to_user_id = $2 AND status = 'pending'"
     )
     .bind(&request_id)
     .bind(&user_id)
@@ -313,6 +591,10 @@ pub async fn decline_friend_request(
     .await;
 
     match result {
+        Ok(r) if r.rows_affected() == 0 => Ok(FriendsResult {
+            success: false,
+            error: Some("Friend request not found".to_string()),
+        }),
         Ok(_) => Ok(FriendsResult {
             success: true,
             error: None,
@@ -330,11 +612,15 @@ pub async fn cancel_friend_request(
     request_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<FriendsResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
+    expire_friend_requests(pool.as_ref(), &user_id).await?;
+
+    // Kept as a cancelled row so it shows up in request history
     let result = sqlx::query(
-        "DELETE FROM friend_requests WHERE id = $1::uuid AND from_user_id = $2"
+        "UPDATE friend_requests SET status = 'cancelled', responded_at = NOW()
+         WHERE id = $1::uuid AND from_user_id = $2 AND status = 'pending'"
     )
     .bind(&request_id)
     .bind(&user_id)
@@ -342,6 +628,10 @@ pub async fn cancel_friend_request(
     .await;
 
     match result {
+        Ok(r) if r.rows_affected() == 0 => Ok(FriendsResult {
+            success: false,
+            error: Some("Friend request not found".to_string()),
+        }),
         Ok(_) => Ok(FriendsResult {
             success: true,
             error: None,
@@ -357,38 +647,204 @@ pub async fn cancel_friend_request(
 // FRIENDS LIST COMMANDS
 // ============================================
 
-/// Get all friends for the current user
+/// Load the user's friends with organization, presence and DM details in one query
+async fn fetch_friends(
+    user_id: &str,
+    category_id: Option<String>,
+    favorites_only: Option<bool>,
+    sort_by: Option<String>,
+) -> Result<Vec<FriendWithProfile>, String> {
+    let pool = get_pool();
+
+    let category_id = category_id
+        .map(|id| Uuid::parse_str(&id).map_err(|_| "Invalid category".to_string()))
+        .transpose()?;
+
+    // Local nicknames take precedence over the friend's own nickname when sorting by name
+    let order_by = match sort_by.as_deref().unwrap_or("name") {
+        "name" => "lower(COALESCE(f.local_nickname, p.nickname))",
+        "recent" => "f.created_at DESC",
+        "favorites" => "f.is_favorite DESC, lower(COALESCE(f.local_nickname, p.nickname))",
+        "unread" => "unread_count DESC, lower(COALESCE(f.local_nickname, p.nickname))",
+        _ => {
+            return Err(format!(
+                "Invalid sort option. Must be one of: {}",
+                FRIEND_SORT_OPTIONS.join(", ")
+            ));
+        }
+    };
+
+    // The DM is found through its canonical "a:b" key (byte order, as in
+    // get_or_create_dm_conversation), so the unique key index is used
+    let friends: Vec<FriendWithProfile> = sqlx::query_as(&format!(
+        r#"
+        SELECT f.friend_id, p.username, p.nickname, f.created_at::text,
+               f.is_favorite, f.category_id::text AS category_id, c.name AS category_name,
+               f.note, f.local_nickname, p.avatar_url,
+               CASE WHEN v.visible THEN p.status END AS status,
+               CASE WHEN v.visible THEN p.custom_status END AS custom_status,
+               CASE WHEN v.visible THEN p.last_seen_at::text END AS last_seen_at,
+               COALESCE(v.visible AND p.status IN ('online', 'idle', 'dnd'), false) AS is_online,
+               dm.id::text AS dm_conversation_id,
+               COALESCE(dm.unread_count, 0) AS unread_count
+        FROM friends f
+        JOIN profiles p ON f.friend_id = p.user_id
+        LEFT JOIN friend_categories c ON c.id = f.category_id
+        CROSS JOIN LATERAL (SELECT can_see_status(f.friend_id, $1) AS visible) v
+        LEFT JOIN LATERAL (
+            SELECT dc.id,
+                   (SELECT COUNT(*) FROM messages m
+                    WHERE m.conversation_id = dc.id AND m.sender_id <> $1
+                    AND m.timestamp > COALESCE(EXTRACT(EPOCH FROM cp.last_read_at) * 1000, 0)
+                   ) AS unread_count
+            FROM conversations dc
+            JOIN conversation_participants cp ON cp.conversation_id = dc.id AND cp.user_id = $1
+            WHERE dc.type = 'direct'
+            AND dc.dm_participant_key = LEAST($1 COLLATE "C", f.friend_id)
+                || ':' || GREATEST($1 COLLATE "C", f.friend_id)
+        ) dm ON TRUE
+        WHERE f.user_id = $1
+          AND ($2::uuid IS NULL OR f.category_id = $2)
+          AND (NOT $3 OR f.is_favorite)
+        ORDER BY {}
+        "#,
+        order_by
+    ))
+    .bind(user_id)
+    .bind(category_id)
+    .bind(favorites_only.unwrap_or(false))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(friends)
+}
+
+/// Get the current user's friends, optionally filtered to one category or to favorites.
+/// `sort_by` is one of `FRIEND_SORT_OPTIONS` and defaults to "name".
 #[command]
 pub async fn get_friends(
+    category_id: Option<String>,
+    favorites_only: Option<bool>,
+    sort_by: Option<String>,
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<FriendWithProfile>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
+    fetch_friends(&user_id, category_id, favorites_only, sort_by).await
+}
+
+/// Same as `get_friends`, split into online (online, idle, dnd) and offline groups
+#[command]
+pub async fn get_friends_by_presence(
+    category_id: Option<String>,
+    favorites_only: Option<bool>,
+    sort_by: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsByPresence, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let friends = fetch_friends(&user_id, category_id, favorites_only, sort_by).await?;
+
+    let (online, offline) = friends.into_iter().partition(|friend| friend.is_online);
+
+    Ok(FriendsByPresence { online, offline })
+}
+
+/// Update the current user's private organization of one friend: favorite flag, category,
+/// note and local nickname. Fields passed as `None` are left unchanged; an empty string
+/// clears the category, note or nickname.
+#[command]
+pub async fn update_friend_details(
+    friend_id: String,
+    is_favorite: Option<bool>,
+    category_id: Option<String>,
+    note: Option<String>,
+    local_nickname: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
-    // Join with profiles to get friend info
-    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
-        "SELECT f.friend_id, p.username, p.nickname, f.created_at::text
-         FROM friends f
-         JOIN profiles p ON f.friend_id = p.user_id
-         WHERE f.user_id = $1
-         ORDER BY p.nickname"
+    let note = note.map(|n| n.trim().to_string());
+    let local_nickname = local_nickname.map(|n| n.trim().to_string());
+
+    if note.as_ref().is_some_and(|n| n.chars().count() > MAX_NOTE_LENGTH) {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some(format!("Notes must be at most {} characters", MAX_NOTE_LENGTH)),
+        });
+    }
+
+    if local_nickname
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_LOCAL_NICKNAME_LENGTH)
+    {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some(format!(
+                "Nicknames must be at most {} characters",
+                MAX_LOCAL_NICKNAME_LENGTH
+            )),
+        });
+    }
+
+    let category_id = match category_id.map(|id| id.trim().to_string()) {
+        Some(id) if id.is_empty() => Some(id),
+        Some(id) => {
+            // The category must be one of the current user's own
+            let category: Option<(Uuid,)> = match Uuid::parse_str(&id) {
+                Ok(id) => sqlx::query_as(
+                    "SELECT id FROM friend_categories WHERE id = $1 AND user_id = $2"
+                )
+                .bind(id)
+                .bind(&user_id)
+                .fetch_optional(pool.as_ref())
+                .await
+                .map_err(|e| format!("Database error: {}", e))?,
+                Err(_) => None,
+            };
+
+            match category {
+                Some((id,)) => Some(id.to_string()),
+                None => {
+                    return Ok(FriendsResult {
+                        success: false,
+                        error: Some("Category not found".to_string()),
+                    });
+                }
+            }
+        }
+        None => None,
+    };
+
+    let result = sqlx::query(
+        "UPDATE friends SET
+            is_favorite = COALESCE($3, is_favorite),
+            category_id = NULLIF(COALESCE($4, category_id::text), '')::uuid,
+            note = NULLIF(COALESCE($5, note), ''),
+            local_nickname = NULLIF(COALESCE($6, local_nickname), '')
+         WHERE user_id = $1 AND friend_id = $2"
     )
     .bind(&user_id)
-    .fetch_all(pool.as_ref())
+    .bind(&friend_id)
+    .bind(is_favorite)
+    .bind(category_id)
+    .bind(&note)
+    .bind(&local_nickname)
+    .execute(pool.as_ref())
     .await
     .map_err(|e| format!("Database error: {}", e))?;
 
-    let results: Vec<FriendWithProfile> = rows
-        .into_iter()
-        .map(|(friend_id, username, nickname, created_at)| FriendWithProfile {
-            friend_id,
-            username,
-            nickname,
-            created_at,
-        })
-        .collect();
+    if result.rows_affected() == 0 {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Friend not found".to_string()),
+        });
+    }
 
-    Ok(results)
+    Ok(FriendsResult {
+        success: true,
+        error: None,
+    })
 }
 
 /// Remove a friend
@@ -397,26 +853,635 @@ pub async fn remove_friend(
     friend_id: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<FriendsResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    lock_friend_pair(&mut tx, &user_id, &friend_id).await?;
+
     // Remove both directions of the friendship
     let result = sqlx::query(
         "DELETE FROM friends WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)"
     )
     .bind(&user_id)
     .bind(&friend_id)
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(e) = result {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some(format!("Failed to remove friend: {}", e)),
+        });
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(FriendsResult {
+        success: true,
+        error: None,
+    })
+}
+
+// ============================================
+// FRIEND CATEGORY COMMANDS
+// ============================================
+
+/// Trim and validate a category name
+fn validate_category_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Category name is required".to_string());
+    }
+    if name.chars().count() > MAX_CATEGORY_NAME_LENGTH {
+        return Err(format!(
+            "Category names must be at most {} characters",
+            MAX_CATEGORY_NAME_LENGTH
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Get the current user's friend categories with how many friends are in each
+#[command]
+pub async fn get_friend_categories(
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<FriendCategory>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let categories: Vec<FriendCategory> = sqlx::query_as(
+        "SELECT c.id::text AS id, c.name, COUNT(f.id) AS friend_count, c.created_at::text AS created_at
+         FROM friend_categories c
+         LEFT JOIN friends f ON f.category_id = c.id AND f.user_id = c.user_id
+         WHERE c.user_id = $1
+         GROUP BY c.id
+         ORDER BY lower(c.name)"
+    )
+    .bind(&user_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(categories)
+}
+
+/// Create a friend category
+#[command]
+pub async fn create_friend_category(
+    name: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendCategoryResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let name = match validate_category_name(&name) {
+        Ok(name) => name,
+        Err(e) => {
+            return Ok(FriendCategoryResult {
+                success: false,
+                category_id: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    // Count and insert under a per-user lock, so concurrent creates can't pass the limit
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+        .bind(format!("friend_categories:{}", user_id))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM friend_categories WHERE user_id = $1"
+    )
+    .bind(&user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if count >= MAX_CATEGORIES {
+        return Ok(FriendCategoryResult {
+            success: false,
+            category_id: None,
+            error: Some(format!("You can have at most {} categories", MAX_CATEGORIES)),
+        });
+    }
+
+    let result: Result<(String,), _> = sqlx::query_as(
+        "INSERT INTO friend_categories (user_id, name) VALUES ($1, $2) RETURNING id::text"
+    )
+    .bind(&user_id)
+    .bind(&name)
+    .fetch_one(&mut *tx)
+    .await;
+
+    match result {
+        Ok((category_id,)) => {
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+            Ok(FriendCategoryResult {
+                success: true,
+                category_id: Some(category_id),
+                error: None,
+            })
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(FriendCategoryResult {
+            success: false,
+            category_id: None,
+            error: Some("You already have a category with this name".to_string()),
+        }),
+        Err(e) => Ok(FriendCategoryResult {
+            success: false,
+            category_id: None,
+            error: Some(format!("Failed to create category: {}", e)),
+        }),
+    }
+}
+
+/// Rename one of the current user's friend categories
+#[command]
+pub async fn rename_friend_category(
+    category_id: String,
+    name: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let name = match validate_category_name(&name) {
+        Ok(name) => name,
+        Err(e) => {
+            return Ok(FriendsResult {
+                success: false,
+                error: Some(e),
+            });
+        }
+    };
+
+    let Ok(category_id) = Uuid::parse_str(&category_id) else {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Category not found".to_string()),
+        });
+    };
+
+    let result = sqlx::query(
+        "UPDATE friend_categories SET name = $3 WHERE id = $1 AND user_id = $2"
+    )
+    .bind(category_id)
+    .bind(&user_id)
+    .bind(&name)
     .execute(pool.as_ref())
     .await;
 
     match result {
+        Ok(done) if done.rows_affected() == 0 => Ok(FriendsResult {
+            success: false,
+            error: Some("Category not found".to_string()),
+        }),
         Ok(_) => Ok(FriendsResult {
             success: true,
             error: None,
         }),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(FriendsResult {
+            success: false,
+            error: Some("You already have a category with this name".to_string()),
+        }),
         Err(e) => Ok(FriendsResult {
             success: false,
-            error: Some(format!("Failed to remove friend: {}", e)),
+            error: Some(format!("Failed to rename category: {}", e)),
         }),
     }
-}
\ No newline at end of file
+}
+
+/// Delete one of the current user's friend categories; its friends become uncategorized
+#[command]
+pub async fn delete_friend_category(
+    category_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let Ok(category_id) = Uuid::parse_str(&category_id) else {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Category not found".to_string()),
+        });
+    };
+
+    let result = sqlx::query("DELETE FROM friend_categories WHERE id = $1 AND user_id = $2")
+        .bind(category_id)
+        .bind(&user_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Category not found".to_string()),
+        });
+    }
+
+    Ok(FriendsResult {
+        success: true,
+        error: None,
+    })
+}
+
+// ============================================
+// BLOCK COMMANDS
+// ============================================
+
+/// Block a user, ending any friendship and pending requests between you
+#[command]
+pub async fn block_user(
+    user_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let blocker_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    if uuid::Uuid::parse_str(&user_id).is_err() {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("Invalid user ID".to_string()),
+        });
+    }
+
+    if user_id == blocker_id {
+        return Ok(FriendsResult {
+            success: false,
+            error: Some("You cannot block yourself".to_string()),
+        });
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    lock_friend_pair(&mut tx, &blocker_id, &user_id).await?;
+
+    sqlx::query(
+        "INSERT INTO blocked_users (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+    )
+    .bind(&blocker_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    // Remove both directions of the friendship
+    sqlx::query(
+        "DELETE FROM friends WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)"
+    )
+    .bind(&blocker_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    // Cancel pending requests in either direction
+    sqlx::query(
+        "UPDATE friend_requests SET status = 'cancelled', responded_at = NOW()
+         WHERE status = 'pending'
+         AND ((from_user_id = $1 AND to_user_id = $2) OR (from_user_id = $2 AND to_user_id = $1))"
+    )
+    .bind(&blocker_id)
+    .bind(&user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(FriendsResult {
+        success: true,
+        error: None,
+    })
+}
+
+/// Unblock a previously blocked user
+#[command]
+pub async fn unblock_user(
+    user_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let blocker_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let result = sqlx::query(
+        "DELETE FROM blocked_users WHERE blocker_id = $1 AND blocked_id = $2"
+    )
+    .bind(&blocker_id)
+    .bind(&user_id)
+    .execute(pool.as_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(FriendsResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(FriendsResult {
+            success: false,
+            error: Some(format!("Failed to unblock user: {}", e)),
+        }),
+    }
+}
+
+/// Get all users blocked by the current user
+#[command]
+pub async fn get_blocked_users(
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<BlockedUser>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let blocked: Vec<BlockedUser> = sqlx::query_as(
+        "SELECT b.blocked_id AS user_id, p.username, p.nickname, p.avatar_url,
+                b.created_at::text AS blocked_at
+         FROM blocked_users b
+         LEFT JOIN profiles p ON b.blocked_id = p.user_id
+         WHERE b.blocker_id = $1
+         ORDER BY b.created_at DESC"
+    )
+    .bind(&user_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(blocked)
+}
+
+// ============================================
+// FRIEND SUGGESTION COMMANDS
+// ============================================
+
+/// Suggest people to add, ranked by mutual friends and shared group conversations.
+/// Friends, blocked users, pending requests and dismissed suggestions are excluded.
+#[command]
+pub async fn get_friend_suggestions(
+    limit: Option<i64>,
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<FriendSuggestion>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let rows: Vec<SuggestionRow> = sqlx::query_as(
+        r#"
+        WITH mutuals AS (
+            -- Friends of my friends, with up to three of the connecting friends
+            SELECT f2.friend_id AS candidate,
+                   COUNT(*) AS mutual_friend_count,
+                   (ARRAY_AGG(f2.user_id ORDER BY f2.created_at DESC))[1:3] AS mutual_friend_ids
+            FROM friends f1
+            JOIN friends f2 ON f2.user_id = f1.friend_id
+            WHERE f1.user_id = $1
+            GROUP BY f2.friend_id
+        ),
+        groups AS (
+            -- People in my group (non-DM) conversations
+            SELECT other.user_id AS candidate,
+                   COUNT(DISTINCT other.conversation_id) AS shared_group_count
+            FROM conversation_participants mine
+            JOIN conversations c ON c.id = mine.conversation_id AND c.type <> 'direct'
+            JOIN conversation_participants other
+              ON other.conversation_id = mine.conversation_id AND other.user_id <> $1
+            WHERE mine.user_id = $1
+            GROUP BY other.user_id
+        ),
+        candidates AS (
+            SELECT COALESCE(m.candidate, g.candidate) AS candidate,
+                   COALESCE(m.mutual_friend_count, 0) AS mutual_friend_count,
+                   COALESCE(g.shared_group_count, 0) AS shared_group_count,
+                   COALESCE(m.mutual_friend_ids, '{}') AS mutual_friend_ids
+            FROM mutuals m
+            FULL OUTER JOIN groups g ON g.candidate = m.candidate
+        )
+        SELECT c.candidate AS user_id, p.username, p.nickname, p.avatar_url,
+               c.mutual_friend_count, c.shared_group_count, c.mutual_friend_ids
+        FROM candidates c
+        JOIN profiles p ON p.user_id = c.candidate
+        WHERE c.candidate <> $1
+          AND NOT EXISTS (SELECT 1 FROM friends f WHERE f.user_id = $1 AND f.friend_id = c.candidate)
+          AND NOT EXISTS (
+              SELECT 1 FROM friend_requests fr
+              WHERE fr.status = 'pending'
+              AND ((fr.from_user_id = $1 AND fr.to_user_id = c.candidate)
+                OR (fr.from_user_id = c.candidate AND fr.to_user_id = $1)))
+          AND NOT EXISTS (
+              SELECT 1 FROM dismissed_suggestions d
+              WHERE d.user_id = $1 AND d.dismissed_user_id = c.candidate)
+          -- Also covers blocks in either direction
+          AND can_send_friend_request(c.candidate, $1)
+        ORDER BY c.mutual_friend_count * 2 + c.shared_group_count DESC,
+                 c.mutual_friend_count DESC,
+                 p.nickname
+        LIMIT $2
+        "#
+    )
+    .bind(&user_id)
+    .bind(limit.unwrap_or(10).clamp(1, 50))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    // Load every mutual friend preview in one query
+    let preview_ids: Vec<String> = rows
+        .iter()
+        .flat_map(|row| row.mutual_friend_ids.iter().cloned())
+        .collect();
+
+    let previews: Vec<MutualFriendPreview> = if preview_ids.is_empty() {
+        vec![]
+    } else {
+        sqlx::query_as(
+            "SELECT user_id, nickname, avatar_url FROM profiles WHERE user_id = ANY($1)"
+        )
+        .bind(&preview_ids)
+        .fetch_all(pool.as_ref())
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+    };
+
+    let results: Vec<FriendSuggestion> = rows
+        .into_iter()
+        .map(|row| FriendSuggestion {
+            mutual_friends: row
+                .mutual_friend_ids
+                .iter()
+                .filter_map(|id| previews.iter().find(|p| &p.user_id == id).cloned())
+                .collect(),
+            user_id: row.user_id,
+            username: row.username,
+            nickname: row.nickname,
+            avatar_url: row.avatar_url,
+            mutual_friend_count: row.mutual_friend_count,
+            shared_group_count: row.shared_group_count,
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Hide a user from future friend suggestions
+#[command]
+pub async fn dismiss_friend_suggestion(
+    user_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendsResult, String> {
+    let current_user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let result = sqlx::query(
+        "INSERT INTO dismissed_suggestions (user_id, dismissed_user_id) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(&current_user_id)
+    .bind(&user_id)
+    .execute(pool.as_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(FriendsResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(FriendsResult {
+            success: false,
+            error: Some(format!("Failed to dismiss suggestion: {}", e)),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    /// Create two users with profiles, returning `(user_id, username)` for each
+    async fn create_user_pair() -> ((String, String), (String, String)) {
+        let pool = get_pool();
+        let mut users = Vec::new();
+
+        for _ in 0..2 {
+            let user_id = Uuid::new_v4().to_string();
+            let username = format!("test_{}", &user_id[..8]);
+            sqlx::query("INSERT INTO profiles (user_id, username, nickname) VALUES ($1, $2, $2)")
+                .bind(&user_id)
+                .bind(&username)
+                .execute(pool.as_ref())
+                .await
+                .expect("insert profile");
+            users.push((user_id, username));
+        }
+
+        let second = users.pop().unwrap();
+        let first = users.pop().unwrap();
+        (first, second)
+    }
+
+    async fn delete_users(user_a: &str, user_b: &str) {
+        let pool = get_pool();
+        for statement in [
+            "DELETE FROM friends WHERE user_id IN ($1, $2) OR friend_id IN ($1, $2)",
+            "DELETE FROM friend_requests WHERE from_user_id IN ($1, $2) OR to_user_id IN ($1, $2)",
+            "DELETE FROM profiles WHERE user_id IN ($1, $2)",
+        ] {
+            sqlx::query(statement)
+                .bind(user_a)
+                .bind(user_b)
+                .execute(pool.as_ref())
+                .await
+                .expect("clean up test users");
+        }
+    }
+
+    /// Exactly one friendship (a row in each direction) and nothing left pending
+    async fn assert_single_friendship(user_a: &str, user_b: &str) {
+        let pool = get_pool();
+
+        let (friend_rows, pending): (i64, i64) = sqlx::query_as(
+            "SELECT
+                (SELECT COUNT(*) FROM friends
+                 WHERE (user_id = $1 AND friend_id = $2) OR (user_id = $2 AND friend_id = $1)),
+                (SELECT COUNT(*) FROM friend_requests
+                 WHERE status = 'pending'
+                   AND ((from_user_id = $1 AND to_user_id = $2) OR (from_user_id = $2 AND to_user_id = $1)))"
+        )
+        .bind(user_a)
+        .bind(user_b)
+        .fetch_one(pool.as_ref())
+        .await
+        .expect("count friendship rows");
+
+        assert_eq!(friend_rows, 2, "expected one friendship row per direction");
+        assert_eq!(pending, 0, "expected no pending requests left");
+    }
+
+    /// Needs DATABASE_URL pointing at a disposable database with the base schema
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_friend_lifecycle_creates_one_friendship() {
+        if std::env::var("DATABASE_URL").is_err() {
+            eprintln!("DATABASE_URL not set; skipping");
+            return;
+        }
+        init_db().await.expect("initialize database");
+
+        for _ in 0..20 {
+            // Both users send a request to each other at the same time
+            let ((a_id, a_name), (b_id, b_name)) = create_user_pair().await;
+            let (a_sent, b_sent) = tokio::join!(
+                tokio::spawn(send_friend_request_from(a_id.clone(), b_name.clone(), None)),
+                tokio::spawn(send_friend_request_from(b_id.clone(), a_name.clone(), None)),
+            );
+            assert!(a_sent.unwrap().unwrap().success);
+            assert!(b_sent.unwrap().unwrap().success);
+            assert_single_friendship(&a_id, &b_id).await;
+            delete_users(&a_id, &b_id).await;
+
+            // The recipient accepts while also sending a request back
+            let ((a_id, a_name), (b_id, b_name)) = create_user_pair().await;
+            assert!(send_friend_request_from(a_id.clone(), b_name, None).await.unwrap().success);
+            let (request_id,): (String,) = sqlx::query_as(
+                "SELECT id::text FROM friend_requests WHERE from_user_id = $1 AND status = 'pending'"
+            )
+            .bind(&a_id)
+            .fetch_one(get_pool().as_ref())
+            .await
+            .expect("find pending request");
+
+            let (accepted, sent_back) = tokio::join!(
+                tokio::spawn(accept_friend_request_as(b_id.clone(), request_id)),
+                tokio::spawn(send_friend_request_from(b_id.clone(), a_name, None)),
+            );
+            let successes = [accepted.unwrap().unwrap(), sent_back.unwrap().unwrap()]
+                .iter()
+                .filter(|result| result.success)
+                .count();
+            assert_eq!(successes, 1, "exactly one of accept and send-back should win");
+            assert_single_friendship(&a_id, &b_id).await;
+            delete_users(&a_id, &b_id).await;
+        }
+    }
+}
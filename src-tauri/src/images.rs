@@ -0,0 +1,140 @@
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits, Luma};
+use qrcode::QrCode;
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+
+/// Square sizes (width, height in px) generated for every avatar
+pub const AVATAR_SIZES: [(u32, u32); 3] = [(64, 64), (128, 128), (512, 512)];
+
+/// Wide 3:1 sizes generated for profile banners, smallest first
+pub const BANNER_SIZES: [(u32, u32); 2] = [(600, 200), (1500, 500)];
+
+/// Formats accepted as upload input, detected from magic bytes
+const ALLOWED_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
+/// MIME types matching `ALLOWED_FORMATS`, for checks made before the bytes are available
+pub const ALLOWED_CONTENT_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// Minimum edge length of rendered QR codes, in px
+const QR_MIN_SIZE: u32 = 512;
+
+/// Largest dimension accepted before decoding (guards against decompression bombs)
+const MAX_DIMENSION: u32 = 8192;
+
+/// Upper bound on memory the decoder may allocate (256MB)
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// A resized, metadata-free WebP rendition of an image
+pub struct ImageVariant {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl ImageVariant {
+    /// Object name used when storing this variant, e.g. `128x128.webp`
+    pub fn file_name(&self) -> String {
+        format!("{}x{}.webp", self.width, self.height)
+    }
+}
+
+/// Output of the image pipeline
+pub struct ProcessedImage {
+    /// Hex SHA-256 of the original upload, used to key the stored objects
+    pub content_hash: String,
+    pub variants: Vec<ImageVariant>,
+}
+
+/// Detect the real image format from magic bytes, ignoring any caller-supplied type
+pub fn sniff_format(bytes: &[u8]) -> Result<ImageFormat, String> {
+    match image::guess_format(bytes) {
+        Ok(format) if ALLOWED_FORMATS.contains(&format) => Ok(format),
+        _ => Err("File is not a supported image (PNG, JPEG, GIF or WebP)".to_string()),
+    }
+}
+
+/// Decode an image with size limits and EXIF orientation applied.
+/// Re-encoding the decoded pixels drops EXIF/GPS and every other metadata block.
+fn decode(bytes: &[u8], format: ImageFormat) -> Result<DynamicImage, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Invalid image: {}", e))?;
+    let orientation = decoder
+        .orientation()
+        .map_err(|e| format!("Invalid image: {}", e))?;
+
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Invalid image: {}", e))?;
+    image.apply_orientation(orientation);
+
+    Ok(image)
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    // The WebP encoder only takes 8-bit buffers, so normalize 16-bit/float inputs first
+    DynamicImage::ImageRgba8(image.to_rgba8())
+        .write_with_encoder(WebPEncoder::new_lossless(&mut bytes))
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(bytes)
+}
+
+/// Hex SHA-256 of arbitrary bytes
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Validate an uploaded image and produce center-cropped WebP variants of the given sizes.
+/// CPU-bound, so callers should run this on a blocking thread.
+pub fn process_image(bytes: &[u8], sizes: &[(u32, u32)]) -> Result<ProcessedImage, String> {
+    let format = sniff_format(bytes)?;
+    let image = decode(bytes, format)?;
+
+    let variants = sizes
+        .iter()
+        .map(|&(width, height)| {
+            let resized = image.resize_to_fill(width, height, FilterType::Lanczos3);
+            Ok(ImageVariant {
+                width,
+                height,
+                bytes: encode_webp(&resized)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(ProcessedImage {
+        content_hash: content_hash(bytes),
+        variants,
+    })
+}
+
+/// Render `data` as a black-on-white QR code PNG
+pub fn render_qr_png(data: &str) -> Result<Vec<u8>, String> {
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| format!("Failed to generate QR code: {}", e))?;
+    let image = code
+        .render::<Luma<u8>>()
+        .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+        .build();
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    Ok(bytes)
+}
@@ -0,0 +1,338 @@
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::config::friend_request_expiry_days;
+use crate::db::get_pool;
+use crate::deep_links::{friend_invite_url, parse_deep_link, DeepLink};
+use crate::friends::{create_friendship, expire_friend_requests, lock_friend_pair};
+use crate::images::render_qr_png;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use tauri::{command, State};
+use uuid::Uuid;
+
+/// Invite lifetime bounds, in hours
+const DEFAULT_INVITE_HOURS: i64 = 7 * 24;
+const MAX_INVITE_HOURS: i64 = 30 * 24;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// A freshly minted invite, as a link and a QR code encoding that link
+#[derive(Serialize)]
+pub struct FriendInvite {
+    pub success: bool,
+    pub invite_id: Option<String>,
+    pub link: Option<String>,
+    /// Base64 PNG of a QR code for `link`
+    pub qr_code_png: Option<String>,
+    pub expires_at: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Outcome of opening someone's invite link
+#[derive(Serialize, Clone)]
+pub struct InviteRedemption {
+    pub success: bool,
+    pub inviter_id: Option<String>,
+    /// True if the users are now friends, false if a friend request was sent instead
+    pub became_friends: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct InviteResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl InviteRedemption {
+    pub(crate) fn failed(message: &str) -> Self {
+        Self {
+            success: false,
+            inviter_id: None,
+            became_friends: false,
+            error: Some(message.to_string()),
+        }
+    }
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+/// The token handed out is the invite id itself. A random v4 id can't be guessed, and
+/// the `friend_invites` row decides whether it is still valid.
+fn invite_token(invite_id: &Uuid) -> String {
+    invite_id.simple().to_string()
+}
+
+/// Extract the token from an invite link. A bare token is accepted as-is.
+fn parse_invite_link(link: &str) -> Option<String> {
+    let link = link.trim();
+    if !link.contains("://") {
+        return (!link.is_empty() && !link.contains('/')).then(|| link.to_string());
+    }
+
+    match parse_deep_link(link) {
+        Ok(DeepLink::FriendInvite { token }) => Some(token),
+        _ => None,
+    }
+}
+
+/// Turn an invite into a friendship or a pending request for the signed-in user.
+/// Shared by the command and the deep-link router.
+pub(crate) async fn redeem_invite_link(
+    session_store: &SessionStore,
+    link: &str,
+) -> Result<InviteRedemption, String> {
+    let user_id = get_user_id_from_store(session_store).await?;
+
+    let Some(token) = parse_invite_link(link) else {
+        return Ok(InviteRedemption::failed("This invite link is invalid"));
+    };
+    let Ok(invite_id) = Uuid::parse_str(&token) else {
+        return Ok(InviteRedemption::failed("This invite link is invalid"));
+    };
+
+    let pool = get_pool();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // Row lock so a single-use invite can't be redeemed twice concurrently
+    let invite: Option<(String, bool, bool, bool, bool, bool)> = sqlx::query_as(
+        "SELECT inviter_id, single_use, auto_accept,
+                expires_at <= NOW(), revoked_at IS NOT NULL, used_at IS NOT NULL
+         FROM friend_invites WHERE id = $1
+         FOR UPDATE"
+    )
+    .bind(invite_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let (inviter_id, single_use, auto_accept, expired, revoked, used) = match invite {
+        Some(invite) => invite,
+        None => return Ok(InviteRedemption::failed("This invite link is invalid")),
+    };
+
+    if revoked {
+        return Ok(InviteRedemption::failed("This invite link has been revoked"));
+    }
+    if expired {
+        return Ok(InviteRedemption::failed("This invite link has expired"));
+    }
+    if single_use && used {
+        return Ok(InviteRedemption::failed("This invite link has already been used"));
+    }
+    if inviter_id == user_id {
+        return Ok(InviteRedemption::failed("You cannot use your own invite link"));
+    }
+
+    lock_friend_pair(&mut tx, &user_id, &inviter_id).await?;
+    expire_friend_requests(&mut *tx, &user_id).await?;
+
+    // Don't reveal the block; the link just stops working
+    let (blocked,): (bool,) = sqlx::query_as("SELECT is_blocked_between($1, $2)")
+        .bind(&user_id)
+        .bind(&inviter_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    if blocked {
+        return Ok(InviteRedemption::failed("This invite link is invalid"));
+    }
+
+    let existing_friend: Option<(String,)> = sqlx::query_as(
+        "SELECT id::text FROM friends WHERE user_id = $1 AND friend_id = $2"
+    )
+    .bind(&user_id)
+    .bind(&inviter_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if existing_friend.is_some() {
+        return Ok(InviteRedemption::failed("You are already friends with this user"));
+    }
+
+    // A pending request from the inviter means they already want this friendship
+    let became_friends = if auto_accept {
+        true
+    } else {
+        let inviter_request: Option<(String,)> = sqlx::query_as(
+            "SELECT id::text FROM friend_requests
+             WHERE from_user_id = $1 AND to_user_id = $2 AND status = 'pending'"
+        )
+        .bind(&inviter_id)
+        .bind(&user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        inviter_request.is_some()
+    };
+
+    if became_friends {
+        sqlx::query(
+            "UPDATE friend_requests SET status = 'accepted', responded_at = NOW()
+             WHERE status = 'pending'
+               AND ((from_user_id = $1 AND to_user_id = $2) OR (from_user_id = $2 AND to_user_id = $1))"
+        )
+        .bind(&user_id)
+        .bind(&inviter_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        create_friendship(&mut tx, &user_id, &inviter_id).await?;
+    } else {
+        // The invite stands in for the inviter's privacy setting; an existing
+        // request from us is left as it is
+        sqlx::query(
+            "INSERT INTO friend_requests (from_user_id, to_user_id, status, expires_at)
+             VALUES ($1, $2, 'pending', NOW() + $3 * INTERVAL '1 day')
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(&user_id)
+        .bind(&inviter_id)
+        .bind(friend_request_expiry_days())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    if single_use {
+        sqlx::query("UPDATE friend_invites SET used_at = NOW(), used_by = $2 WHERE id = $1")
+            .bind(invite_id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(InviteRedemption {
+        success: true,
+        inviter_id: Some(inviter_id),
+        became_friends,
+        error: None,
+    })
+}
+
+// ============================================
+// INVITE COMMANDS
+// ============================================
+
+/// Create a shareable friend invite link and matching QR code.
+/// `auto_accept` decides whether opening it makes you friends right away or sends you a request.
+#[command]
+pub async fn create_friend_invite(
+    expires_in_hours: Option<i64>,
+    single_use: bool,
+    auto_accept: bool,
+    session_store: State<'_, SessionStore>,
+) -> Result<FriendInvite, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let hours = expires_in_hours.unwrap_or(DEFAULT_INVITE_HOURS);
+    if !(1..=MAX_INVITE_HOURS).contains(&hours) {
+        return Ok(FriendInvite {
+            success: false,
+            invite_id: None,
+            link: None,
+            qr_code_png: None,
+            expires_at: None,
+            error: Some(format!(
+                "Invites must expire within 1 to {} hours",
+                MAX_INVITE_HOURS
+            )),
+        });
+    }
+
+    let invite_id = Uuid::new_v4();
+    let expires_at = Utc::now() + Duration::hours(hours);
+
+    let link = friend_invite_url(&invite_token(&invite_id));
+    let qr_link = link.clone();
+    let qr_code = tokio::task::spawn_blocking(move || render_qr_png(&qr_link))
+        .await
+        .map_err(|e| format!("QR code task failed: {}", e))??;
+
+    sqlx::query(
+        "INSERT INTO friend_invites (id, inviter_id, single_use, auto_accept, expires_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(invite_id)
+    .bind(&user_id)
+    .bind(single_use)
+    .bind(auto_accept)
+    .bind(expires_at)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Failed to create invite: {}", e))?;
+
+    Ok(FriendInvite {
+        success: true,
+        invite_id: Some(invite_id.to_string()),
+        link: Some(link),
+        qr_code_png: Some(STANDARD.encode(qr_code)),
+        expires_at: Some(expires_at.to_rfc3339()),
+        error: None,
+    })
+}
+
+/// Revoke one of the current user's invites so its link stops working
+#[command]
+pub async fn revoke_friend_invite(
+    invite_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<InviteResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let Ok(invite_id) = Uuid::parse_str(&invite_id) else {
+        return Ok(InviteResult {
+            success: false,
+            error: Some("Invite not found".to_string()),
+        });
+    };
+
+    let result = sqlx::query(
+        "UPDATE friend_invites SET revoked_at = NOW()
+         WHERE id = $1 AND inviter_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(invite_id)
+    .bind(&user_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Ok(InviteResult {
+            success: false,
+            error: Some("Invite not found".to_string()),
+        });
+    }
+
+    Ok(InviteResult {
+        success: true,
+        error: None,
+    })
+}
+
+/// Redeem an invite from a `cryptex://add-friend/<token>` link (or its bare token)
+#[command]
+pub async fn redeem_friend_invite(
+    link: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<InviteRedemption, String> {
+    redeem_invite_link(&session_store, &link).await
+}
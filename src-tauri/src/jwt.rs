@@ -0,0 +1,178 @@
+use crate::config::{aws_region, cognito_client_id, cognito_user_pool_id};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long fetched signing keys are trusted before the JWKS is fetched again
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum gap between refetches triggered by an unknown key id, so forged
+/// `kid`s can't turn every request into a JWKS download
+const JWKS_MIN_REFETCH: Duration = Duration::from_secs(60);
+
+/// Clock skew tolerated on `exp`/`nbf`, in seconds
+const CLOCK_LEEWAY_SECS: u64 = 60;
+
+static JWKS_CACHE: RwLock<Option<JwksCache>> = RwLock::const_new(None);
+
+// ============================================
+// TYPES
+// ============================================
+
+/// Signing keys of the user pool, by key id
+struct JwksCache {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kid: String,
+    kty: String,
+    n: String,
+    e: String,
+}
+
+/// Claims read from a verified Cognito ID token
+#[derive(Deserialize, Debug)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    #[serde(default)]
+    pub email: String,
+    token_use: String,
+}
+
+/// Claims read from a verified Cognito access token
+#[derive(Deserialize, Debug)]
+pub struct AccessTokenClaims {
+    pub sub: String,
+    pub exp: i64,
+    client_id: String,
+    token_use: String,
+}
+
+// ============================================
+// JWKS
+// ============================================
+
+/// Issuer every token from our user pool carries
+fn issuer() -> String {
+    format!(
+        "https://cognito-idp.{}.amazonaws.com/{}",
+        aws_region(),
+        cognito_user_pool_id()
+    )
+}
+
+async fn fetch_jwks() -> Result<HashMap<String, DecodingKey>, String> {
+    let url = format!("{}/.well-known/jwks.json", issuer());
+    let jwks: Jwks = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch signing keys: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid signing keys: {}", e))?;
+
+    let mut keys = HashMap::new();
+    for jwk in jwks.keys.into_iter().filter(|jwk| jwk.kty == "RSA") {
+        let key = DecodingKey::from_rsa_components(&jwk.n, &jwk.e)
+            .map_err(|e| format!("Invalid signing key {}: {}", jwk.kid, e))?;
+        keys.insert(jwk.kid, key);
+    }
+    Ok(keys)
+}
+
+/// Find the signing key for `kid`, refetching the JWKS when the cache is stale
+/// or the key is unknown (Cognito rotated its keys)
+async fn signing_key(kid: &str) -> Result<DecodingKey, String> {
+    {
+        let cache = JWKS_CACHE.read().await;
+        if let Some(cache) = cache.as_ref() {
+            let fresh = cache.fetched_at.elapsed() < JWKS_TTL;
+            match cache.keys.get(kid) {
+                Some(key) if fresh => return Ok(key.clone()),
+                None if cache.fetched_at.elapsed() < JWKS_MIN_REFETCH => {
+                    return Err("Token signed with an unknown key".to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut cache = JWKS_CACHE.write().await;
+    // Another caller may have refetched while we waited for the lock
+    let recently_fetched = cache
+        .as_ref()
+        .is_some_and(|cache| cache.fetched_at.elapsed() < JWKS_MIN_REFETCH);
+    if !recently_fetched {
+        *cache = Some(JwksCache {
+            keys: fetch_jwks().await?,
+            fetched_at: Instant::now(),
+        });
+    }
+
+    cache
+        .as_ref()
+        .and_then(|cache| cache.keys.get(kid).cloned())
+        .ok_or_else(|| "Token signed with an unknown key".to_string())
+}
+
+// ============================================
+// VERIFICATION
+// ============================================
+
+/// Check the signature, issuer, expiry and (if set) audience of a Cognito token
+async fn verify<T: serde::de::DeserializeOwned>(
+    token: &str,
+    audience: Option<&str>,
+) -> Result<T, String> {
+    let header = decode_header(token).map_err(|e| format!("Invalid token: {}", e))?;
+    if header.alg != Algorithm::RS256 {
+        return Err("Invalid token: unexpected signing algorithm".to_string());
+    }
+    let kid = header.kid.ok_or("Invalid token: missing key id")?;
+    let key = signing_key(&kid).await?;
+
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.leeway = CLOCK_LEEWAY_SECS;
+    validation.set_issuer(&[issuer()]);
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+    match audience {
+        Some(audience) => validation.set_audience(&[audience]),
+        None => validation.validate_aud = false,
+    }
+
+    decode::<T>(token, &key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| format!("Invalid token: {}", e))
+}
+
+/// Verify an ID token issued by our user pool to our app client
+pub async fn verify_id_token(token: &str) -> Result<IdTokenClaims, String> {
+    let claims: IdTokenClaims = verify(token, Some(&cognito_client_id())).await?;
+    if claims.token_use != "id" {
+        return Err("Invalid token: not an ID token".to_string());
+    }
+    Ok(claims)
+}
+
+/// Verify an access token issued by our user pool to our app client.
+/// Cognito access tokens carry `client_id` instead of `aud`.
+pub async fn verify_access_token(token: &str) -> Result<AccessTokenClaims, String> {
+    let claims: AccessTokenClaims = verify(token, None).await?;
+    if claims.token_use != "access" {
+        return Err("Invalid token: not an access token".to_string());
+    }
+    if claims.client_id != cognito_client_id() {
+        return Err("Invalid token: issued to a different client".to_string());
+    }
+    Ok(claims)
+}
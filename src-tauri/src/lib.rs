@@ -1,27 +1,62 @@
 // Module declarations
+mod account_api;
 mod auth;
 mod config;
 mod conversations;
 mod db;
+mod deep_links;
 mod friends;
+mod images;
+mod invites;
+mod jwt;
+mod mfa;
+mod oauth;
+mod moderation;
+mod privacy;
 mod profile;
+mod session_vault;
+mod sessions;
+mod srp;
+mod uploads;
 
 // Re-export the Tauri commands so they can be used in main
 pub use auth::{
-    confirm_sign_up, get_auth_token, get_session, get_user_id, get_websocket_url,
-    refresh_session, sign_in, sign_out, sign_up, sync_oauth_session, SessionStore,
+    change_email, change_password, confirm_email_change, confirm_forgot_password,
+    confirm_sign_up, forgot_password, get_auth_token, get_session, get_user_id,
+    get_websocket_url, refresh_session, resend_confirmation_code, sign_in, sign_out,
+    sign_out_everywhere, sign_up, update_phone_number, verify_phone_number, SessionStore,
 };
 pub use conversations::{
     get_conversations, get_messages, get_or_create_dm_conversation, mark_conversation_read,
     send_message,
 };
+pub use deep_links::{flush_pending_deep_links, DeepLinkQueue};
 pub use friends::{
-    accept_friend_request, cancel_friend_request, decline_friend_request, get_friends,
-    get_incoming_friend_requests, get_outgoing_friend_requests, remove_friend, send_friend_request,
+    accept_friend_request, block_user, cancel_friend_request, create_friend_category,
+    decline_friend_request, delete_friend_category, dismiss_friend_suggestion,
+    get_blocked_users, get_friend_categories, get_friend_request_history,
+    get_friend_suggestions, get_friends, get_friends_by_presence,
+    get_incoming_friend_requests, get_outgoing_friend_requests, remove_friend,
+    rename_friend_category, send_friend_request, unblock_user, update_friend_details,
 };
+pub use invites::{create_friend_invite, redeem_friend_invite, revoke_friend_invite};
+pub use mfa::{
+    associate_totp, disable_mfa, enable_sms_mfa, get_mfa_status, regenerate_recovery_codes,
+    respond_to_mfa_challenge, sign_in_with_recovery_code, verify_totp,
+};
+pub use moderation::{get_reports, report_message, report_user, resolve_report, unsuspend_user};
+pub use oauth::{start_oauth_sign_in, OAuthFlow};
+pub use privacy::{get_privacy_settings, update_privacy_settings};
 pub use profile::{
-    check_profile_exists, create_profile, delete_profile_image, generate_placeholder_profile,
-    get_profile, get_profiles_by_ids, update_profile, update_status, upload_profile_image,
+    check_profile_exists, confirm_avatar_upload, create_profile, delete_profile_banner,
+    delete_profile_image, generate_placeholder_profile, get_profile, get_profiles_by_ids,
+    get_user_profile, request_avatar_upload, search_users, update_custom_status,
+    update_profile, update_profile_details, update_status, upload_profile_banner,
+    upload_profile_image,
+};
+pub use sessions::{forget_device, get_active_sessions, get_remembered_devices, revoke_session};
+pub use uploads::{
+    cancel_file_upload, get_pending_uploads, resume_file_upload, start_file_upload,
 };
 
 use db::init_db;
@@ -46,10 +81,7 @@ pub fn run() {
 
             // Find the deep link URL in arguments
             if let Some(url) = argv.iter().find(|arg| arg.starts_with("cryptex://")) {
-                println!("Deep link URL: {}", url);
-                // Emit event to frontend
-                use tauri::Emitter;
-                let _ = app.emit("deep-link", url);
+                deep_links::handle_deep_link(app, url);
             }
 
             // Focus the existing window
@@ -59,7 +91,7 @@ pub fn run() {
             }
         }))
         // Setup hook to initialize database
-        .setup(|_app| {
+        .setup(|app| {
             // Initialize database connection pool
             tauri::async_runtime::block_on(async {
                 if let Err(e) = init_db().await {
@@ -67,48 +99,133 @@ pub fn run() {
                     // You might want to show an error dialog here
                 }
             });
+
+            // Keep tokens fresh in the background, then bring back the session saved by the last run
+            auth::spawn_token_refresh(app.handle());
+            auth::restore_persisted_session(app.handle());
+
+            // Route links that launched the app, then any the OS delivers while running
+            use tauri_plugin_deep_link::DeepLinkExt;
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    deep_links::handle_deep_link(&handle, url.as_str());
+                }
+            });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                for url in urls {
+                    deep_links::handle_deep_link(app.handle(), url.as_str());
+                }
+            }
             Ok(())
         })
         // Initialize the session store as managed state
         .manage(SessionStore::default())
+        // Deep links are queued here until the frontend is listening
+        .manage(DeepLinkQueue::default())
+        // Hosted UI sign-in waiting for its callback link
+        .manage(OAuthFlow::default())
         // Register all Tauri commands
         .invoke_handler(tauri::generate_handler![
             // Auth commands
             sign_in,
             sign_up,
             sign_out,
+            sign_out_everywhere,
             get_session,
             get_auth_token,
             get_user_id,
             refresh_session,
-            sync_oauth_session,
+            start_oauth_sign_in,
             confirm_sign_up,
+            resend_confirmation_code,
+            forgot_password,
+            confirm_forgot_password,
+            change_password,
+            change_email,
+            confirm_email_change,
+            update_phone_number,
+            verify_phone_number,
             get_websocket_url,
+            // Session and device commands
+            get_active_sessions,
+            revoke_session,
+            get_remembered_devices,
+            forget_device,
+            // MFA commands
+            respond_to_mfa_challenge,
+            sign_in_with_recovery_code,
+            get_mfa_status,
+            associate_totp,
+            verify_totp,
+            enable_sms_mfa,
+            disable_mfa,
+            regenerate_recovery_codes,
             // Profile commands
             check_profile_exists,
             get_profile,
             get_profiles_by_ids,
+            get_user_profile,
+            search_users,
             create_profile,
             update_profile,
+            update_profile_details,
             upload_profile_image,
+            request_avatar_upload,
+            confirm_avatar_upload,
             delete_profile_image,
+            upload_profile_banner,
+            delete_profile_banner,
             update_status,
+            update_custom_status,
             generate_placeholder_profile,
+            // Privacy commands
+            get_privacy_settings,
+            update_privacy_settings,
             // Friends commands
             send_friend_request,
             get_incoming_friend_requests,
             get_outgoing_friend_requests,
+            get_friend_request_history,
             accept_friend_request,
             decline_friend_request,
             cancel_friend_request,
             get_friends,
+            get_friends_by_presence,
+            update_friend_details,
             remove_friend,
+            get_friend_categories,
+            create_friend_category,
+            rename_friend_category,
+            delete_friend_category,
+            block_user,
+            unblock_user,
+            get_blocked_users,
+            get_friend_suggestions,
+            dismiss_friend_suggestion,
+            // Invite commands
+            create_friend_invite,
+            revoke_friend_invite,
+            redeem_friend_invite,
             // Conversation commands
             get_or_create_dm_conversation,
             get_conversations,
             get_messages,
             send_message,
             mark_conversation_read,
+            // Moderation commands
+            report_message,
+            report_user,
+            get_reports,
+            resolve_report,
+            unsuspend_user,
+            // Deep link commands
+            flush_pending_deep_links,
+            // Upload commands
+            start_file_upload,
+            resume_file_upload,
+            get_pending_uploads,
+            cancel_file_upload,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
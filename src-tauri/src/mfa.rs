@@ -0,0 +1,478 @@
+use crate::account_api;
+use crate::auth::{
+    complete_sign_in, create_cognito_client, get_access_token_from_store,
+    srp_custom_authenticate, AuthResult, SessionStore,
+};
+use crate::config::cognito_client_id;
+use crate::images::render_qr_png;
+use aws_sdk_cognitoidentityprovider::types::{
+    ChallengeNameType, SmsMfaSettingsType, SoftwareTokenMfaSettingsType,
+    VerifySoftwareTokenResponseType,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+/// Issuer shown next to the account in authenticator apps
+const TOTP_ISSUER: &str = "Cryptex";
+
+// ============================================
+// TYPES
+// ============================================
+
+#[derive(Serialize)]
+pub struct MfaResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Which second factors the current user has turned on
+#[derive(Serialize)]
+pub struct MfaStatus {
+    pub success: bool,
+    pub totp_enabled: bool,
+    pub sms_enabled: bool,
+    /// `SOFTWARE_TOKEN_MFA` or `SMS_MFA`
+    pub preferred: Option<String>,
+    /// None when the count could not be loaded; `error` then says why
+    pub recovery_codes_remaining: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// Secret for a new authenticator, as text and as a scannable `otpauth://` QR code
+#[derive(Serialize)]
+pub struct TotpSetup {
+    pub success: bool,
+    pub secret: Option<String>,
+    pub otpauth_uri: Option<String>,
+    /// Base64-encoded PNG
+    pub qr_code_png: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Recovery codes, returned in plain text only when they are issued
+#[derive(Serialize)]
+pub struct RecoveryCodes {
+    pub success: bool,
+    pub codes: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl RecoveryCodes {
+    fn failed(error: String) -> Self {
+        Self {
+            success: false,
+            codes: Vec::new(),
+            error: Some(error),
+        }
+    }
+}
+
+// ============================================
+// RECOVERY CODES
+// ============================================
+
+// Recovery codes live server-side, so holding the app's credentials is not enough to mint
+// or redeem one. The account API issues, counts and discards them for the caller's access
+// token; redeeming one is a `CUSTOM_CHALLENGE` answered after SRP, checked by the user
+// pool's auth triggers, which also turn MFA off for the account.
+
+#[derive(Deserialize)]
+struct IssuedCodes {
+    codes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RemainingCodes {
+    remaining: i64,
+}
+
+/// Account API resource holding the signed-in user's recovery codes
+const RECOVERY_CODES_PATH: &str = "/mfa/recovery-codes";
+
+/// Replace the user's recovery codes with a fresh set and return them
+async fn issue_recovery_codes(access_token: &str) -> Result<Vec<String>, String> {
+    let issued: IssuedCodes =
+        account_api::request_json(Method::POST, RECOVERY_CODES_PATH, access_token, None::<&()>).await?;
+    Ok(issued.codes)
+}
+
+async fn count_recovery_codes(access_token: &str) -> Result<i64, String> {
+    let remaining: RemainingCodes =
+        account_api::request_json(Method::GET, RECOVERY_CODES_PATH, access_token, None::<&()>).await?;
+    Ok(remaining.remaining)
+}
+
+async fn delete_recovery_codes(access_token: &str) -> Result<(), String> {
+    account_api::request(Method::DELETE, RECOVERY_CODES_PATH, access_token, None::<&()>).await?;
+    Ok(())
+}
+
+/// Recovery codes to hand out after MFA is turned on: a fresh set the first time,
+/// nothing if the user still has unused codes from before. MFA is already on by then,
+/// so a failure is reported in `error` and the user can call `regenerate_recovery_codes`.
+async fn recovery_codes_after_enable(access_token: &str) -> RecoveryCodes {
+    let codes = match count_recovery_codes(access_token).await {
+        Ok(remaining) if remaining > 0 => Ok(Vec::new()),
+        Ok(_) => issue_recovery_codes(access_token).await,
+        Err(e) => Err(e),
+    };
+
+    match codes {
+        Ok(codes) => RecoveryCodes {
+            success: true,
+            codes,
+            error: None,
+        },
+        Err(e) => RecoveryCodes {
+            success: true,
+            codes: Vec::new(),
+            error: Some(format!(
+                "MFA is on, but recovery codes could not be created ({}). Please generate them again.",
+                e
+            )),
+        },
+    }
+}
+
+// ============================================
+// MFA SETTINGS COMMANDS
+// ============================================
+
+/// Get the current user's MFA settings
+#[command]
+pub async fn get_mfa_status(session_store: State<'_, SessionStore>) -> Result<MfaStatus, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let user = match client.get_user().access_token(&access_token).send().await {
+        Ok(user) => user,
+        Err(e) => {
+            return Ok(MfaStatus {
+                success: false,
+                totp_enabled: false,
+                sms_enabled: false,
+                preferred: None,
+                recovery_codes_remaining: None,
+                error: Some(format!("Failed to load MFA settings: {:?}", e.into_service_error())),
+            });
+        }
+    };
+
+    // The factors come from Cognito; the recovery code count is best effort
+    let (recovery_codes_remaining, error) = match count_recovery_codes(&access_token).await {
+        Ok(remaining) => (Some(remaining), None),
+        Err(e) => (None, Some(format!("Failed to load recovery codes: {}", e))),
+    };
+
+    let enabled = user.user_mfa_setting_list();
+    Ok(MfaStatus {
+        success: true,
+        totp_enabled: enabled.iter().any(|mfa| mfa == "SOFTWARE_TOKEN_MFA"),
+        sms_enabled: enabled.iter().any(|mfa| mfa == "SMS_MFA"),
+        preferred: user.preferred_mfa_setting().map(str::to_string),
+        recovery_codes_remaining,
+        error,
+    })
+}
+
+/// Start adding an authenticator app. Returns the shared secret; MFA is not
+/// turned on until `verify_totp` confirms a code from the app.
+#[command]
+pub async fn associate_totp(session_store: State<'_, SessionStore>) -> Result<TotpSetup, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let email = session_store
+        .session
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|session| session.email.clone())
+        .unwrap_or_default();
+    let client = create_cognito_client().await;
+
+    let secret = match client
+        .associate_software_token()
+        .access_token(access_token)
+        .send()
+        .await
+    {
+        Ok(response) => response.secret_code().unwrap_or_default().to_string(),
+        Err(e) => {
+            return Ok(TotpSetup {
+                success: false,
+                secret: None,
+                otpauth_uri: None,
+                qr_code_png: None,
+                error: Some(format!("Failed to start authenticator setup: {:?}", e.into_service_error())),
+            });
+        }
+    };
+
+    let label: String = url::form_urlencoded::byte_serialize(format!("{}:{}", TOTP_ISSUER, email).as_bytes()).collect();
+    let otpauth_uri = format!(
+        "otpauth://totp/{}?secret={}&issuer={}",
+        label, secret, TOTP_ISSUER
+    );
+
+    let qr_uri = otpauth_uri.clone();
+    let qr_code = tokio::task::spawn_blocking(move || render_qr_png(&qr_uri))
+        .await
+        .map_err(|e| format!("QR code task failed: {}", e))??;
+
+    Ok(TotpSetup {
+        success: true,
+        secret: Some(secret),
+        otpauth_uri: Some(otpauth_uri),
+        qr_code_png: Some(STANDARD.encode(qr_code)),
+        error: None,
+    })
+}
+
+/// Finish adding an authenticator app with a code from it, and make it the
+/// preferred second factor. Recovery codes are returned the first time MFA is enabled.
+#[command]
+pub async fn verify_totp(
+    code: String,
+    device_name: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<RecoveryCodes, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .verify_software_token()
+        .access_token(&access_token)
+        .user_code(code.trim())
+        .set_friendly_device_name(device_name.filter(|name| !name.trim().is_empty()))
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status() == Some(&VerifySoftwareTokenResponseType::Success) => {}
+        Ok(_) => return Ok(RecoveryCodes::failed("Invalid verification code".to_string())),
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                err if err.is_code_mismatch_exception()
+                    || err.is_enable_software_token_mfa_exception() => "Invalid verification code".to_string(),
+                err if err.is_too_many_requests_exception() => {
+                    "Too many attempts. Please try again later".to_string()
+                }
+                err => format!("Authenticator setup failed: {:?}", err),
+            };
+            return Ok(RecoveryCodes::failed(error_message));
+        }
+    }
+
+    let result = client
+        .set_user_mfa_preference()
+        .access_token(&access_token)
+        .software_token_mfa_settings(
+            SoftwareTokenMfaSettingsType::builder()
+                .enabled(true)
+                .preferred_mfa(true)
+                .build(),
+        )
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        return Ok(RecoveryCodes::failed(format!(
+            "Failed to enable MFA: {:?}",
+            e.into_service_error()
+        )));
+    }
+
+    Ok(recovery_codes_after_enable(&access_token).await)
+}
+
+/// Turn on SMS codes as a second factor. Needs a verified phone number.
+#[command]
+pub async fn enable_sms_mfa(session_store: State<'_, SessionStore>) -> Result<RecoveryCodes, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .set_user_mfa_preference()
+        .access_token(&access_token)
+        .sms_mfa_settings(SmsMfaSettingsType::builder().enabled(true).build())
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        let error_message = match e.into_service_error() {
+            err if err.is_invalid_parameter_exception() => {
+                "Verify a phone number before turning on SMS codes".to_string()
+            }
+            err => format!("Failed to enable MFA: {:?}", err),
+        };
+        return Ok(RecoveryCodes::failed(error_message));
+    }
+
+    Ok(recovery_codes_after_enable(&access_token).await)
+}
+
+/// Turn off every second factor and discard the recovery codes
+#[command]
+pub async fn disable_mfa(session_store: State<'_, SessionStore>) -> Result<MfaResult, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .set_user_mfa_preference()
+        .access_token(&access_token)
+        .software_token_mfa_settings(SoftwareTokenMfaSettingsType::builder().enabled(false).build())
+        .sms_mfa_settings(SmsMfaSettingsType::builder().enabled(false).build())
+        .send()
+        .await;
+
+    if let Err(e) = result {
+        return Ok(MfaResult {
+            success: false,
+            error: Some(format!("Failed to disable MFA: {:?}", e.into_service_error())),
+        });
+    }
+
+    // MFA is already off, so leftover codes could only turn it off again; report the
+    // failure without undoing the change
+    let error = delete_recovery_codes(&access_token)
+        .await
+        .err()
+        .map(|e| format!("MFA is off, but old recovery codes could not be discarded ({})", e));
+
+    Ok(MfaResult {
+        success: true,
+        error,
+    })
+}
+
+/// Replace the current user's recovery codes, invalidating the old ones
+#[command]
+pub async fn regenerate_recovery_codes(
+    session_store: State<'_, SessionStore>,
+) -> Result<RecoveryCodes, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+
+    match issue_recovery_codes(&access_token).await {
+        Ok(codes) => Ok(RecoveryCodes {
+            success: true,
+            codes,
+            error: None,
+        }),
+        Err(e) => Ok(RecoveryCodes::failed(format!("Failed to create recovery codes: {}", e))),
+    }
+}
+
+// ============================================
+// SIGN-IN CHALLENGE COMMANDS
+// ============================================
+
+fn challenge_failed(error: &str) -> AuthResult {
+    AuthResult {
+        success: false,
+        error: Some(error.to_string()),
+        user_id: None,
+        needs_confirmation: false,
+        challenge: None,
+    }
+}
+
+/// Finish a sign-in that `sign_in` paused on an MFA challenge
+#[command]
+pub async fn respond_to_mfa_challenge(
+    code: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    let Some(pending) = session_store.take_pending_challenge() else {
+        return Ok(challenge_failed("Sign-in expired. Please sign in again"));
+    };
+
+    let code_key = match pending.name {
+        ChallengeNameType::SmsMfa => "SMS_MFA_CODE",
+        _ => "SOFTWARE_TOKEN_MFA_CODE",
+    };
+
+    let client = create_cognito_client().await;
+
+    let result = client
+        .respond_to_auth_challenge()
+        .client_id(cognito_client_id())
+        .challenge_name(pending.name.clone())
+        .session(&pending.session)
+        .challenge_responses("USERNAME", &pending.username)
+        .challenge_responses(code_key, code.trim())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => match response.authentication_result() {
+            Some(auth_result) => complete_sign_in(&session_store, auth_result).await,
+            None => Ok(challenge_failed("No authentication result")),
+        },
+        Err(e) => match e.into_service_error() {
+            err if err.is_code_mismatch_exception() => {
+                session_store.retry_pending_challenge(pending);
+                Ok(challenge_failed("Invalid verification code"))
+            }
+            err if err.is_expired_code_exception() || err.is_not_authorized_exception() => {
+                Ok(challenge_failed("Sign-in expired. Please sign in again"))
+            }
+            err if err.is_too_many_requests_exception() => {
+                Ok(challenge_failed("Too many attempts. Please try again later"))
+            }
+            err => Ok(challenge_failed(&format!("Authentication failed: {:?}", err))),
+        },
+    }
+}
+
+/// Finish a sign-in paused on an MFA challenge with a recovery code instead. The code is
+/// checked by the user pool's auth triggers after the password is proven again with SRP;
+/// on success they turn MFA off so the user can set up a new authenticator.
+#[command]
+pub async fn sign_in_with_recovery_code(
+    recovery_code: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<AuthResult, String> {
+    let Some(pending) = session_store.take_pending_challenge() else {
+        return Ok(challenge_failed("Sign-in expired. Please sign in again"));
+    };
+
+    let client = create_cognito_client().await;
+
+    let challenge = match srp_custom_authenticate(&client, &pending.username, &pending.password).await {
+        Ok(response) if response.challenge_name() == Some(&ChallengeNameType::CustomChallenge) => response,
+        Ok(_) => return Ok(challenge_failed("Recovery codes are not available. Please try again later")),
+        Err(err) => return Ok(challenge_failed(&format!("Authentication failed: {}", err.message))),
+    };
+
+    let result = client
+        .respond_to_auth_challenge()
+        .client_id(cognito_client_id())
+        .challenge_name(ChallengeNameType::CustomChallenge)
+        .set_session(challenge.session().map(str::to_string))
+        .challenge_responses("USERNAME", &pending.username)
+        .challenge_responses("ANSWER", recovery_code.trim())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) => match response.authentication_result() {
+            Some(auth_result) => complete_sign_in(&session_store, auth_result).await,
+            // The triggers ask again after a wrong code
+            None => {
+                session_store.retry_pending_challenge(pending);
+                Ok(challenge_failed("Invalid recovery code"))
+            }
+        },
+        Err(e) => match e.into_service_error() {
+            err if err.is_not_authorized_exception() => {
+                session_store.retry_pending_challenge(pending);
+                Ok(challenge_failed("Invalid recovery code"))
+            }
+            err if err.is_too_many_requests_exception() => {
+                Ok(challenge_failed("Too many attempts. Please try again later"))
+            }
+            err => Ok(challenge_failed(&format!("Authentication failed: {:?}", err))),
+        },
+    }
+}
@@ -0,0 +1,521 @@
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::conversations::Message;
+use crate::db::get_pool;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use tauri::{command, State};
+
+// ============================================
+// CONSTANTS
+// ============================================
+
+/// Valid report reason categories
+pub const REPORT_REASONS: [&str; 8] = [
+    "spam",
+    "harassment",
+    "hate_speech",
+    "violence",
+    "sexual_content",
+    "impersonation",
+    "self_harm",
+    "other",
+];
+
+/// Valid moderator actions when resolving a report
+pub const RESOLUTION_ACTIONS: [&str; 3] = ["dismiss", "warn", "suspend"];
+
+/// Longest free-text explanation accepted with a report
+const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+
+/// Messages captured before and after a reported message
+const REPORT_CONTEXT_MESSAGES: i64 = 5;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// Report as shown in the moderator queue
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct ReportSummary {
+    pub id: String,
+    pub reporter_id: String,
+    pub target_type: String,
+    pub reported_user_id: String,
+    pub message_id: Option<String>,
+    pub reason: String,
+    pub details: Option<String>,
+    pub snapshot: serde_json::Value,
+    pub status: String,
+    pub resolution_note: Option<String>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReportResult {
+    pub success: bool,
+    pub report_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ModerationResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Reported user's profile as it looked when the report was filed
+#[derive(Serialize, FromRow)]
+struct ProfileSnapshot {
+    user_id: String,
+    username: String,
+    nickname: String,
+    avatar_url: Option<String>,
+    bio: Option<String>,
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+/// Validate reason and details, returning the trimmed details
+fn validate_report(reason: &str, details: Option<String>) -> Result<Option<String>, String> {
+    if !REPORT_REASONS.contains(&reason) {
+        return Err(format!(
+            "Invalid reason. Must be one of: {}",
+            REPORT_REASONS.join(", ")
+        ));
+    }
+
+    let details = details
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+
+    if details.as_ref().is_some_and(|d| d.chars().count() > MAX_REPORT_DETAILS_LENGTH) {
+        return Err(format!(
+            "Details must be at most {} characters",
+            MAX_REPORT_DETAILS_LENGTH
+        ));
+    }
+
+    Ok(details)
+}
+
+async fn profile_snapshot(user_id: &str) -> Result<Option<ProfileSnapshot>, String> {
+    let pool = get_pool();
+
+    sqlx::query_as(
+        "SELECT user_id, username, nickname, avatar_url, bio FROM profiles WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))
+}
+
+/// Error out unless the user is a moderator
+async fn require_moderator(user_id: &str) -> Result<(), String> {
+    let pool = get_pool();
+
+    let moderator: Option<(String,)> = sqlx::query_as(
+        "SELECT user_id FROM moderators WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    match moderator {
+        Some(_) => Ok(()),
+        None => Err("Moderator access required".to_string()),
+    }
+}
+
+async fn insert_report(
+    reporter_id: &str,
+    target_type: &str,
+    reported_user_id: &str,
+    message_id: Option<&str>,
+    reason: &str,
+    details: Option<String>,
+    snapshot: serde_json::Value,
+) -> ReportResult {
+    let pool = get_pool();
+
+    let result: Result<(String,), _> = sqlx::query_as(
+        "INSERT INTO reports (reporter_id, target_type, reported_user_id, message_id, reason, details, snapshot)
+         VALUES ($1, $2, $3, $4::uuid, $5, $6, $7)
+         RETURNING id::text"
+    )
+    .bind(reporter_id)
+    .bind(target_type)
+    .bind(reported_user_id)
+    .bind(message_id)
+    .bind(reason)
+    .bind(&details)
+    .bind(&snapshot)
+    .fetch_one(pool.as_ref())
+    .await;
+
+    match result {
+        Ok((report_id,)) => ReportResult {
+            success: true,
+            report_id: Some(report_id),
+            error: None,
+        },
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => ReportResult {
+            success: false,
+            report_id: None,
+            error: Some("You have already reported this".to_string()),
+        },
+        Err(e) => ReportResult {
+            success: false,
+            report_id: None,
+            error: Some(format!("Failed to submit report: {}", e)),
+        },
+    }
+}
+
+// ============================================
+// REPORT COMMANDS
+// ============================================
+
+/// Report a message, capturing it and its surrounding conversation
+#[command]
+pub async fn report_message(
+    message_id: String,
+    reason: String,
+    details: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<ReportResult, String> {
+    let reporter_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let details = match validate_report(&reason, details) {
+        Ok(details) => details,
+        Err(e) => {
+            return Ok(ReportResult {
+                success: false,
+                report_id: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    if uuid::Uuid::parse_str(&message_id).is_err() {
+        return Ok(ReportResult {
+            success: false,
+            report_id: None,
+            error: Some("Invalid message ID".to_string()),
+        });
+    }
+
+    // Only messages the reporter can actually see may be reported
+    let message: Option<Message> = sqlx::query_as(
+        "SELECT m.id::text, m.conversation_id::text, m.sender_id, m.content, m.timestamp
+         FROM messages m
+         JOIN conversation_participants cp
+           ON cp.conversation_id = m.conversation_id AND cp.user_id = $2
+         WHERE m.id = $1::uuid"
+    )
+    .bind(&message_id)
+    .bind(&reporter_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let message = match message {
+        Some(message) => message,
+        None => {
+            return Ok(ReportResult {
+                success: false,
+                report_id: None,
+                error: Some("Message not found".to_string()),
+            });
+        }
+    };
+
+    if message.sender_id == reporter_id {
+        return Ok(ReportResult {
+            success: false,
+            report_id: None,
+            error: Some("You cannot report your own message".to_string()),
+        });
+    }
+
+    let context: Vec<Message> = sqlx::query_as(
+        "SELECT * FROM (
+            (SELECT id::text, conversation_id::text, sender_id, content, timestamp
+             FROM messages
+             WHERE conversation_id = $1::uuid AND timestamp <= $2 AND id != $3::uuid
+             ORDER BY timestamp DESC LIMIT $4)
+            UNION ALL
+            (SELECT id::text, conversation_id::text, sender_id, content, timestamp
+             FROM messages
+             WHERE conversation_id = $1::uuid AND timestamp > $2
+             ORDER BY timestamp ASC LIMIT $4)
+         ) ctx
+         ORDER BY timestamp ASC"
+    )
+    .bind(&message.conversation_id)
+    .bind(message.timestamp)
+    .bind(&message_id)
+    .bind(REPORT_CONTEXT_MESSAGES)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let reported_user = profile_snapshot(&message.sender_id).await?;
+
+    let snapshot = serde_json::json!({
+        "message": message,
+        "context": context,
+        "reported_user": reported_user,
+    });
+
+    Ok(insert_report(
+        &reporter_id,
+        "message",
+        &message.sender_id,
+        Some(&message_id),
+        &reason,
+        details,
+        snapshot,
+    )
+    .await)
+}
+
+/// Report a user, capturing their current profile
+#[command]
+pub async fn report_user(
+    user_id: String,
+    reason: String,
+    details: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<ReportResult, String> {
+    let reporter_id = get_user_id_from_store(&session_store).await?;
+
+    let details = match validate_report(&reason, details) {
+        Ok(details) => details,
+        Err(e) => {
+            return Ok(ReportResult {
+                success: false,
+                report_id: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    if user_id == reporter_id {
+        return Ok(ReportResult {
+            success: false,
+            report_id: None,
+            error: Some("You cannot report yourself".to_string()),
+        });
+    }
+
+    let reported_user = match profile_snapshot(&user_id).await? {
+        Some(profile) => profile,
+        None => {
+            return Ok(ReportResult {
+                success: false,
+                report_id: None,
+                error: Some("User not found".to_string()),
+            });
+        }
+    };
+
+    let snapshot = serde_json::json!({ "reported_user": reported_user });
+
+    Ok(insert_report(&reporter_id, "user", &user_id, None, &reason, details, snapshot).await)
+}
+
+// ============================================
+// MODERATOR COMMANDS
+// ============================================
+
+/// List reports for moderators, oldest first, optionally filtered by status
+#[command]
+pub async fn get_reports(
+    status: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<ReportSummary>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    require_moderator(&user_id).await?;
+    let pool = get_pool();
+
+    let reports: Vec<ReportSummary> = sqlx::query_as(
+        "SELECT id::text, reporter_id, target_type, reported_user_id, message_id::text,
+                reason, details, snapshot, status, resolution_note,
+                created_at::text, resolved_at::text
+         FROM reports
+         WHERE $1::text IS NULL OR status = $1
+         ORDER BY created_at ASC
+         LIMIT $2 OFFSET $3"
+    )
+    .bind(&status)
+    .bind(limit.unwrap_or(50).clamp(1, 200))
+    .bind(offset.unwrap_or(0).max(0))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(reports)
+}
+
+/// Resolve an open report by dismissing it, warning the user, or suspending them.
+/// `suspend_days` of None suspends indefinitely.
+#[command]
+pub async fn resolve_report(
+    report_id: String,
+    action: String,
+    note: Option<String>,
+    suspend_days: Option<i64>,
+    session_store: State<'_, SessionStore>,
+) -> Result<ModerationResult, String> {
+    let moderator_id = get_user_id_from_store(&session_store).await?;
+    require_moderator(&moderator_id).await?;
+    let pool = get_pool();
+
+    if uuid::Uuid::parse_str(&report_id).is_err() {
+        return Ok(ModerationResult {
+            success: false,
+            error: Some("Report not found".to_string()),
+        });
+    }
+
+    if !RESOLUTION_ACTIONS.contains(&action.as_str()) {
+        return Ok(ModerationResult {
+            success: false,
+            error: Some(format!(
+                "Invalid action. Must be one of: {}",
+                RESOLUTION_ACTIONS.join(", ")
+            )),
+        });
+    }
+
+    if suspend_days.is_some_and(|days| days <= 0) {
+        return Ok(ModerationResult {
+            success: false,
+            error: Some("Suspension length must be at least one day".to_string()),
+        });
+    }
+
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    let status = match action.as_str() {
+        "warn" => "warned",
+        "suspend" => "suspended",
+        _ => "dismissed",
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // Lock the report so two moderators can't resolve it at once
+    let report: Option<(String,)> = sqlx::query_as(
+        "SELECT reported_user_id FROM reports WHERE id = $1::uuid AND status = 'open' FOR UPDATE"
+    )
+    .bind(&report_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    let reported_user_id = match report {
+        Some((id,)) => id,
+        None => {
+            return Ok(ModerationResult {
+                success: false,
+                error: Some("Open report not found".to_string()),
+            });
+        }
+    };
+
+    match action.as_str() {
+        "warn" => {
+            sqlx::query(
+                "INSERT INTO user_warnings (user_id, report_id, reason, issued_by)
+                 VALUES ($1, $2::uuid, $3, $4)"
+            )
+            .bind(&reported_user_id)
+            .bind(&report_id)
+            .bind(&note)
+            .bind(&moderator_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+        }
+        "suspend" => {
+            sqlx::query(
+                "INSERT INTO account_suspensions (user_id, report_id, reason, suspended_until, suspended_by)
+                 VALUES ($1, $2::uuid, $3, NOW() + make_interval(days => $4::int), $5)
+                 ON CONFLICT (user_id) DO UPDATE SET
+                    report_id = EXCLUDED.report_id,
+                    reason = EXCLUDED.reason,
+                    suspended_until = EXCLUDED.suspended_until,
+                    suspended_by = EXCLUDED.suspended_by,
+                    created_at = NOW()"
+            )
+            .bind(&reported_user_id)
+            .bind(&report_id)
+            .bind(&note)
+            .bind(suspend_days)
+            .bind(&moderator_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+        }
+        _ => {}
+    }
+
+    sqlx::query(
+        "UPDATE reports SET status = $1, resolved_by = $2, resolution_note = $3, resolved_at = NOW()
+         WHERE id = $4::uuid"
+    )
+    .bind(status)
+    .bind(&moderator_id)
+    .bind(&note)
+    .bind(&report_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(ModerationResult {
+        success: true,
+        error: None,
+    })
+}
+
+/// Lift a user's suspension
+#[command]
+pub async fn unsuspend_user(
+    user_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<ModerationResult, String> {
+    let moderator_id = get_user_id_from_store(&session_store).await?;
+    require_moderator(&moderator_id).await?;
+    let pool = get_pool();
+
+    let result = sqlx::query("DELETE FROM account_suspensions WHERE user_id = $1")
+        .bind(&user_id)
+        .execute(pool.as_ref())
+        .await;
+
+    match result {
+        Ok(_) => Ok(ModerationResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(ModerationResult {
+            success: false,
+            error: Some(format!("Failed to lift suspension: {}", e)),
+        }),
+    }
+}
@@ -0,0 +1,239 @@
+use crate::auth::{session_from_tokens, start_session, AuthResult, SessionStore};
+use crate::config::{cognito_client_id, cognito_domain};
+use crate::deep_links::oauth_redirect_url;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+use url::Url;
+
+/// Identity providers the hosted UI is allowed to send users to
+const OAUTH_PROVIDERS: [&str; 1] = ["Google"];
+
+/// How long the user has to finish signing in at the provider
+const OAUTH_FLOW_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Random bytes in the PKCE verifier (43 characters once encoded) and in `state`
+const CODE_VERIFIER_LEN: usize = 32;
+const STATE_LEN: usize = 16;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// The OAuth sign-in waiting for its `cryptex://auth/callback`. Only one can be
+/// in flight; starting another replaces it.
+#[derive(Default)]
+pub struct OAuthFlow {
+    pending: Mutex<Option<PendingOAuth>>,
+}
+
+struct PendingOAuth {
+    state: String,
+    code_verifier: String,
+    started_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: String,
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Compare without stopping at the first differing byte
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn failed(error: &str) -> AuthResult {
+    AuthResult {
+        success: false,
+        error: Some(error.to_string()),
+        user_id: None,
+        needs_confirmation: false,
+        challenge: None,
+    }
+}
+
+/// Hosted UI authorize URL for `provider`, with PKCE (S256) and `state`
+fn authorize_url(provider: &str, state: &str, code_verifier: &str) -> Result<Url, String> {
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+    let mut url = Url::parse(&format!("https://{}/oauth2/authorize", cognito_domain()))
+        .map_err(|e| format!("Invalid Cognito domain: {}", e))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &cognito_client_id())
+        .append_pair("redirect_uri", &oauth_redirect_url())
+        .append_pair("identity_provider", provider)
+        .append_pair("scope", "openid email profile")
+        .append_pair("state", state)
+        .append_pair("code_challenge", &code_challenge)
+        .append_pair("code_challenge_method", "S256");
+    Ok(url)
+}
+
+/// Trade the authorization code for tokens at the hosted UI token endpoint
+async fn exchange_code(code: &str, code_verifier: &str) -> Result<TokenResponse, String> {
+    let redirect_url = oauth_redirect_url();
+    let client_id = cognito_client_id();
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("client_id", client_id.as_str()),
+        ("code", code),
+        ("redirect_uri", redirect_url.as_str()),
+        ("code_verifier", code_verifier),
+    ];
+
+    let response = reqwest::Client::new()
+        .post(format!("https://{}/oauth2/token", cognito_domain()))
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| format!("Token exchange failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response
+            .json::<TokenErrorResponse>()
+            .await
+            .map(|body| body.error_description.unwrap_or(body.error))
+            .unwrap_or_else(|_| "unexpected response".to_string());
+        return Err(format!("Token exchange failed: {}", error));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Token exchange failed: {}", e))
+}
+
+// ============================================
+// CALLBACK HANDLING
+// ============================================
+
+/// Finish the pending sign-in from the `cryptex://auth/callback` deep link. The state must
+/// match the flow we started; the code is exchanged here so tokens never reach the frontend.
+pub(crate) async fn complete_oauth_sign_in(
+    app: &AppHandle,
+    code: &str,
+    state: Option<&str>,
+) -> Result<AuthResult, String> {
+    let pending = app
+        .state::<OAuthFlow>()
+        .pending
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take();
+
+    let Some(pending) = pending else {
+        return Ok(failed("No sign-in in progress. Please try again"));
+    };
+    if pending.started_at.elapsed() > OAUTH_FLOW_TTL {
+        return Ok(failed("Sign-in took too long. Please try again"));
+    }
+    if !state.is_some_and(|state| constant_time_eq(state, &pending.state)) {
+        return Ok(failed("Sign-in could not be verified. Please try again"));
+    }
+
+    let tokens = match exchange_code(code, &pending.code_verifier).await {
+        Ok(tokens) => tokens,
+        Err(e) => return Ok(failed(&e)),
+    };
+
+    let session = match session_from_tokens(
+        &tokens.access_token,
+        &tokens.refresh_token,
+        &tokens.id_token,
+    )
+    .await
+    {
+        Ok(session) => session,
+        Err(e) => return Ok(failed(&e)),
+    };
+    let user_id = session.user_id.clone();
+    start_session(&app.state::<SessionStore>(), session).await?;
+
+    Ok(AuthResult {
+        success: true,
+        error: None,
+        user_id: Some(user_id),
+        needs_confirmation: false,
+        challenge: None,
+    })
+}
+
+/// The provider sent the user back with an error (e.g. they cancelled); drop the flow
+pub(crate) fn fail_oauth_sign_in(app: &AppHandle, error: &str, description: Option<&str>) -> AuthResult {
+    if let Ok(mut pending) = app.state::<OAuthFlow>().pending.lock() {
+        *pending = None;
+    }
+    failed(&format!("Sign-in failed: {}", description.unwrap_or(error)))
+}
+
+// ============================================
+// OAUTH COMMANDS
+// ============================================
+
+/// Start signing in with an external identity provider (default Google) in the
+/// system browser. The result arrives later as an `oauth-sign-in` event.
+#[command]
+pub async fn start_oauth_sign_in(
+    provider: Option<String>,
+    app: AppHandle,
+    flow: State<'_, OAuthFlow>,
+) -> Result<AuthResult, String> {
+    let provider = provider.unwrap_or_else(|| OAUTH_PROVIDERS[0].to_string());
+    if !OAUTH_PROVIDERS.contains(&provider.as_str()) {
+        return Ok(failed(&format!(
+            "Unsupported sign-in provider. Must be one of: {}",
+            OAUTH_PROVIDERS.join(", ")
+        )));
+    }
+
+    let state = random_token(STATE_LEN);
+    let code_verifier = random_token(CODE_VERIFIER_LEN);
+    let url = authorize_url(&provider, &state, &code_verifier)?;
+
+    *flow.pending.lock().map_err(|e| e.to_string())? = Some(PendingOAuth {
+        state,
+        code_verifier,
+        started_at: Instant::now(),
+    });
+
+    if let Err(e) = app.opener().open_url(url.as_str(), None::<&str>) {
+        return Ok(failed(&format!("Failed to open the browser: {}", e)));
+    }
+
+    Ok(AuthResult {
+        success: true,
+        error: None,
+        user_id: None,
+        needs_confirmation: false,
+        challenge: None,
+    })
+}
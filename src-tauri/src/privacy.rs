@@ -0,0 +1,169 @@
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::db::get_pool;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor};
+use tauri::{command, State};
+
+// ============================================
+// CONSTANTS
+// ============================================
+
+/// Who can see a user's online status
+pub const STATUS_VISIBILITY_OPTIONS: [&str; 3] = ["everyone", "friends", "nobody"];
+
+/// Who can send a user friend requests
+pub const FRIEND_REQUEST_OPTIONS: [&str; 3] = ["everyone", "friends_of_friends", "nobody"];
+
+/// Who can start a new DM with a user
+pub const DIRECT_MESSAGE_OPTIONS: [&str; 3] = ["everyone", "friends", "nobody"];
+
+// ============================================
+// TYPES
+// ============================================
+
+/// Privacy settings returned to frontend
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct PrivacySettings {
+    pub status_visibility: String,
+    pub friend_requests: String,
+    pub direct_messages: String,
+    /// Whether non-friends can find this user through search
+    pub searchable: bool,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            status_visibility: "everyone".to_string(),
+            friend_requests: "everyone".to_string(),
+            direct_messages: "everyone".to_string(),
+            searchable: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PrivacyResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+/// Whether `requester` may send `owner` a friend request (see the `can_send_friend_request` SQL function)
+pub(crate) async fn can_send_friend_request<'e>(
+    executor: impl PgExecutor<'e>,
+    owner: &str,
+    requester: &str,
+) -> Result<bool, String> {
+    let (allowed,): (bool,) = sqlx::query_as("SELECT can_send_friend_request($1, $2)")
+        .bind(owner)
+        .bind(requester)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(allowed)
+}
+
+/// Whether `requester` may start a new DM with `owner` (see the `can_start_dm` SQL function)
+pub(crate) async fn can_start_dm(owner: &str, requester: &str) -> Result<bool, String> {
+    let pool = get_pool();
+
+    let (allowed,): (bool,) = sqlx::query_as("SELECT can_start_dm($1, $2)")
+        .bind(owner)
+        .bind(requester)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(allowed)
+}
+
+// ============================================
+// PRIVACY COMMANDS
+// ============================================
+
+/// Get the current user's privacy settings
+#[command]
+pub async fn get_privacy_settings(
+    session_store: State<'_, SessionStore>,
+) -> Result<PrivacySettings, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let settings: Option<PrivacySettings> = sqlx::query_as(
+        "SELECT status_visibility, friend_requests, direct_messages, searchable
+         FROM privacy_settings WHERE user_id = $1"
+    )
+    .bind(&user_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+/// Update the current user's privacy settings
+#[command]
+pub async fn update_privacy_settings(
+    status_visibility: String,
+    friend_requests: String,
+    direct_messages: String,
+    searchable: bool,
+    session_store: State<'_, SessionStore>,
+) -> Result<PrivacyResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let checks: [(&str, &String, &[&str]); 3] = [
+        ("status visibility", &status_visibility, &STATUS_VISIBILITY_OPTIONS),
+        ("friend requests", &friend_requests, &FRIEND_REQUEST_OPTIONS),
+        ("direct messages", &direct_messages, &DIRECT_MESSAGE_OPTIONS),
+    ];
+
+    for (label, value, options) in checks {
+        if !options.contains(&value.as_str()) {
+            return Ok(PrivacyResult {
+                success: false,
+                error: Some(format!(
+                    "Invalid {} setting. Must be one of: {}",
+                    label,
+                    options.join(", ")
+                )),
+            });
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO privacy_settings
+            (user_id, status_visibility, friend_requests, direct_messages, searchable)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id) DO UPDATE SET
+            status_visibility = EXCLUDED.status_visibility,
+            friend_requests = EXCLUDED.friend_requests,
+            direct_messages = EXCLUDED.direct_messages,
+            searchable = EXCLUDED.searchable,
+            updated_at = NOW()"
+    )
+    .bind(&user_id)
+    .bind(&status_visibility)
+    .bind(&friend_requests)
+    .bind(&direct_messages)
+    .bind(searchable)
+    .execute(pool.as_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(PrivacyResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(PrivacyResult {
+            success: false,
+            error: Some(format!("Failed to update privacy settings: {}", e)),
+        }),
+    }
+}
@@ -1,18 +1,41 @@
-use crate::auth::SessionStore;
-use crate::config::{s3_bucket, cloudfront_url, aws_region};
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::config::{s3_bucket, s3_endpoint, cloudfront_url, aws_region};
 use crate::db::get_pool;
+use crate::images::{
+    process_image, ProcessedImage, ALLOWED_CONTENT_TYPES, AVATAR_SIZES, BANNER_SIZES,
+};
 use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use rand::seq::SliceRandom;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::HashMap;
+use std::time::Duration;
 use tauri::{command, State};
 
 /// Valid status values
 pub const VALID_STATUSES: [&str; 4] = ["online", "idle", "dnd", "offline"];
 
+/// Largest avatar accepted, in bytes (5MB)
+const MAX_AVATAR_SIZE: usize = 5 * 1024 * 1024;
+
+/// How long a presigned avatar upload URL stays valid
+const AVATAR_UPLOAD_URL_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Limits for the free-form profile fields
+const MAX_BIO_LENGTH: usize = 190;
+const MAX_PRONOUNS_LENGTH: usize = 40;
+const MAX_LINKS: usize = 5;
+const MAX_LINK_LENGTH: usize = 200;
+const MAX_CUSTOM_STATUS_LENGTH: usize = 128;
+
+/// Search query length bounds (characters)
+const MIN_SEARCH_LENGTH: usize = 2;
+const MAX_SEARCH_LENGTH: usize = 50;
+
 /// Word lists for placeholder profile generation
 const ADJECTIVES: &[&str] = &[
     "Swift", "Clever", "Bright", "Bold", "Calm", "Daring", "Eager", "Fancy",
@@ -39,6 +62,26 @@ pub struct ProfileData {
     pub nickname: String,
     pub avatar_url: Option<String>,
     pub status: Option<String>,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    pub banner_url: Option<String>,
+    pub links: Vec<String>,
+    pub timezone: Option<String>,
+}
+
+/// Another user's public profile card
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct UserProfileCard {
+    pub user_id: String,
+    pub username: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub banner_url: Option<String>,
+    pub status: Option<String>,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    pub links: Vec<String>,
+    pub timezone: Option<String>,
 }
 
 /// Result for profile operations
@@ -56,6 +99,17 @@ pub struct ImageUploadResult {
     pub error: Option<String>,
 }
 
+/// Presigned direct-to-S3 upload returned to frontend
+#[derive(Serialize)]
+pub struct AvatarUploadRequest {
+    pub success: bool,
+    pub upload_url: Option<String>,
+    /// Headers the PUT must send exactly as given, since they are part of the signature
+    pub headers: HashMap<String, String>,
+    pub key: Option<String>,
+    pub error: Option<String>,
+}
+
 /// Placeholder profile data for new users
 #[derive(Serialize)]
 pub struct PlaceholderProfile {
@@ -72,32 +126,86 @@ pub struct ProfileNickname {
     pub status: Option<String>,
 }
 
-/// Helper function to get user ID from session store
-fn get_user_id_from_store(session_store: &SessionStore) -> Result<String, String> {
-    let store = session_store
-        .session
-        .lock()
-        .map_err(|e| format!("Failed to lock session: {}", e))?;
-
-    match &*store {
-        Some(session) => {
-            if chrono::Utc::now().timestamp() >= session.expires_at {
-                Err("Session expired. Please sign in again.".to_string())
-            } else {
-                Ok(session.user_id.clone())
-            }
+/// A user matching a search query
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct UserSearchResult {
+    pub user_id: String,
+    pub username: String,
+    pub nickname: String,
+    pub avatar_url: Option<String>,
+    pub status: Option<String>,
+    pub is_friend: bool,
+    pub mutual_friend_count: i64,
+}
+
+/// Trim an optional text field, treating blank input as unset
+pub(crate) fn normalize_optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Validate external profile links, returning the cleaned list
+fn validate_links(links: Vec<String>) -> Result<Vec<String>, String> {
+    let links: Vec<String> = links
+        .into_iter()
+        .map(|link| link.trim().to_string())
+        .filter(|link| !link.is_empty())
+        .collect();
+
+    if links.len() > MAX_LINKS {
+        return Err(format!("You can add at most {} links", MAX_LINKS));
+    }
+
+    for link in &links {
+        let valid = link.len() <= MAX_LINK_LENGTH
+            && url::Url::parse(link)
+                .map(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+                .unwrap_or(false);
+
+        if !valid {
+            return Err(format!("Invalid link: {}", link));
         }
-        None => Err("Not authenticated. Please sign in.".to_string()),
     }
+
+    Ok(links)
+}
+
+/// Escape LIKE wildcards so user input only ever matches literally
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
-/// Create S3 client
-async fn create_s3_client() -> S3Client {
+/// Record activity for the "last seen" time friends see
+pub(crate) async fn touch_last_seen(user_id: &str) -> Result<(), String> {
+    let pool = get_pool();
+
+    sqlx::query("UPDATE profiles SET last_seen_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+/// Create S3 client (honors S3_ENDPOINT for S3-compatible stores like MinIO)
+pub(crate) async fn create_s3_client() -> S3Client {
     let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
         .region(aws_config::Region::new(aws_region()))
         .load()
         .await;
-    S3Client::new(&config)
+
+    let mut s3_config = aws_sdk_s3::config::Builder::from(&config);
+    if let Some(endpoint) = s3_endpoint() {
+        // S3-compatible stores generally don't support virtual-hosted buckets
+        s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+    }
+
+    S3Client::from_conf(s3_config.build())
 }
 
 /// Tauri command to generate placeholder profile data for new users
@@ -115,15 +223,40 @@ pub fn generate_placeholder_profile() -> PlaceholderProfile {
     PlaceholderProfile { username, nickname }
 }
 
-/// Tauri command to upload a profile image to S3
+/// Upload every processed variant under `{prefix}/{content_hash}/` and return the
+/// URL of the last (largest) one; the other sizes sit next to it in the same folder
+async fn store_image_variants(prefix: &str, processed: ProcessedImage) -> Result<String, String> {
+    let s3_client = create_s3_client().await;
+    let mut public_url = None;
+
+    for variant in processed.variants {
+        let key = format!("{}/{}/{}", prefix, processed.content_hash, variant.file_name());
+
+        s3_client
+            .put_object()
+            .bucket(s3_bucket())
+            .key(&key)
+            .body(ByteStream::from(variant.bytes))
+            .content_type("image/webp")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload image: {}", e))?;
+
+        public_url = Some(format!("{}/{}", cloudfront_url(), key));
+    }
+
+    public_url.ok_or_else(|| "No image variants were generated".to_string())
+}
+
+/// Tauri command to upload a profile image to S3.
+/// The real format is sniffed from the bytes, metadata is stripped, and 64/128/512px
+/// WebP variants are stored under `avatars/{user_id}/{sha256}/`.
 #[command]
 pub async fn upload_profile_image(
     image_data: String,
-    file_name: String,
-    content_type: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<ImageUploadResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
 
     // Decode base64 image data
     let image_bytes = STANDARD
@@ -131,7 +264,7 @@ pub async fn upload_profile_image(
         .map_err(|e| format!("Failed to decode image data: {}", e))?;
 
     // Validate image size (max 5MB)
-    if image_bytes.len() > 5 * 1024 * 1024 {
+    if image_bytes.len() > MAX_AVATAR_SIZE {
         return Ok(ImageUploadResult {
             success: false,
             url: None,
@@ -139,35 +272,280 @@ pub async fn upload_profile_image(
         });
     }
 
-    let s3_client = create_s3_client().await;
+    // Decoding and resizing is CPU-bound, keep it off the async runtime
+    let processed = tokio::task::spawn_blocking(move || process_image(&image_bytes, &AVATAR_SIZES))
+        .await
+        .map_err(|e| format!("Image processing failed: {}", e))?;
 
-    // Create unique file path: avatars/{user_id}/{filename}
-    let key = format!("avatars/{}/{}", user_id, file_name);
+    let processed = match processed {
+        Ok(processed) => processed,
+        Err(e) => {
+            return Ok(ImageUploadResult {
+                success: false,
+                url: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    match store_image_variants(&format!("avatars/{}", user_id), processed).await {
+        Ok(public_url) => Ok(ImageUploadResult {
+            success: true,
+            url: Some(public_url),
+            error: None,
+        }),
+        Err(e) => Ok(ImageUploadResult {
+            success: false,
+            url: None,
+            error: Some(e),
+        }),
+    }
+}
+
+/// Tauri command to get a presigned PUT URL for uploading an avatar straight to S3.
+/// The URL only covers a fresh key under `avatars/{user_id}/uploads/` and is signed
+/// for the declared content type and length.
+#[command]
+pub async fn request_avatar_upload(
+    content_type: String,
+    content_length: i64,
+    session_store: State<'_, SessionStore>,
+) -> Result<AvatarUploadRequest, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
 
-    let result = s3_client
+    let rejected = |error: &str| AvatarUploadRequest {
+        success: false,
+        upload_url: None,
+        headers: HashMap::new(),
+        key: None,
+        error: Some(error.to_string()),
+    };
+
+    if !ALLOWED_CONTENT_TYPES.contains(&content_type.as_str()) {
+        return Ok(rejected("Image must be PNG, JPEG, GIF or WebP"));
+    }
+
+    if content_length <= 0 || content_length as usize > MAX_AVATAR_SIZE {
+        return Ok(rejected("Image must be less than 5MB"));
+    }
+
+    let key = format!("avatars/{}/uploads/{}", user_id, uuid::Uuid::new_v4());
+
+    let presigning_config = PresigningConfig::expires_in(AVATAR_UPLOAD_URL_TTL)
+        .map_err(|e| format!("Failed to presign upload: {}", e))?;
+
+    let s3_client = create_s3_client().await;
+    let presigned = s3_client
         .put_object()
         .bucket(s3_bucket())
         .key(&key)
-        .body(ByteStream::from(image_bytes))
         .content_type(&content_type)
-        .send()
+        .content_length(content_length)
+        .presigned(presigning_config)
+        .await;
+
+    match presigned {
+        Ok(request) => Ok(AvatarUploadRequest {
+            success: true,
+            upload_url: Some(request.uri().to_string()),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            key: Some(key),
+            error: None,
+        }),
+        Err(e) => Ok(rejected(&format!("Failed to presign upload: {}", e))),
+    }
+}
+
+/// Tauri command to finish a presigned avatar upload.
+/// Verifies the object, runs it through the image pipeline, and sets `profiles.avatar_url`.
+#[command]
+pub async fn confirm_avatar_upload(
+    key: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<ImageUploadResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let failed = |error: String| ImageUploadResult {
+        success: false,
+        url: None,
+        error: Some(error),
+    };
+
+    // Only accept keys minted by request_avatar_upload for this user
+    let upload_id = key.strip_prefix(&format!("avatars/{}/uploads/", user_id));
+    if upload_id.is_none_or(|id| uuid::Uuid::parse_str(id).is_err()) {
+        return Ok(failed("Invalid upload key".to_string()));
+    }
+
+    let s3_client = create_s3_client().await;
+
+    let head = match s3_client.head_object().bucket(s3_bucket()).key(&key).send().await {
+        Ok(head) => head,
+        Err(_) => return Ok(failed("Upload not found".to_string())),
+    };
+
+    let object = if head.content_length().unwrap_or_default() as usize > MAX_AVATAR_SIZE {
+        Err("Image must be less than 5MB".to_string())
+    } else {
+        match s3_client.get_object().bucket(s3_bucket()).key(&key).send().await {
+            Ok(output) => output
+                .body
+                .collect()
+                .await
+                .map(|data| data.into_bytes().to_vec())
+                .map_err(|e| format!("Failed to read upload: {}", e)),
+            Err(e) => Err(format!("Failed to read upload: {}", e)),
+        }
+    };
+
+    // The raw upload still carries the original metadata, so never keep it around
+    let _ = s3_client.delete_object().bucket(s3_bucket()).key(&key).send().await;
+
+    let image_bytes = match object {
+        Ok(bytes) => bytes,
+        Err(e) => return Ok(failed(e)),
+    };
+
+    let processed = tokio::task::spawn_blocking(move || process_image(&image_bytes, &AVATAR_SIZES))
+        .await
+        .map_err(|e| format!("Image processing failed: {}", e))?;
+
+    let processed = match processed {
+        Ok(processed) => processed,
+        Err(e) => return Ok(failed(e)),
+    };
+
+    let public_url = match store_image_variants(&format!("avatars/{}", user_id), processed).await {
+        Ok(url) => url,
+        Err(e) => return Ok(failed(e)),
+    };
+
+    let result = sqlx::query("UPDATE profiles SET avatar_url = $1 WHERE user_id = $2")
+        .bind(&public_url)
+        .bind(&user_id)
+        .execute(pool.as_ref())
         .await;
 
     match result {
-        Ok(_) => {
-            // Construct CloudFront URL
-            let public_url = format!("{}/{}", cloudfront_url(), key);
+        Ok(_) => Ok(ImageUploadResult {
+            success: true,
+            url: Some(public_url),
+            error: None,
+        }),
+        Err(e) => Ok(failed(format!("Failed to update profile: {}", e))),
+    }
+}
+
+/// Tauri command to upload a profile banner to S3 and set it on the profile.
+/// Goes through the same pipeline as avatars, producing 3:1 WebP variants.
+#[command]
+pub async fn upload_profile_banner(
+    image_data: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<ImageUploadResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let image_bytes = STANDARD
+        .decode(&image_data)
+        .map_err(|e| format!("Failed to decode image data: {}", e))?;
+
+    if image_bytes.len() > MAX_AVATAR_SIZE {
+        return Ok(ImageUploadResult {
+            success: false,
+            url: None,
+            error: Some("Image must be less than 5MB".to_string()),
+        });
+    }
+
+    let processed = tokio::task::spawn_blocking(move || process_image(&image_bytes, &BANNER_SIZES))
+        .await
+        .map_err(|e| format!("Image processing failed: {}", e))?;
+
+    let public_url = match processed {
+        Ok(processed) => store_image_variants(&format!("banners/{}", user_id), processed).await,
+        Err(e) => Err(e),
+    };
+
+    let public_url = match public_url {
+        Ok(url) => url,
+        Err(e) => {
+            return Ok(ImageUploadResult {
+                success: false,
+                url: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    let result = sqlx::query("UPDATE profiles SET banner_url = $1 WHERE user_id = $2")
+        .bind(&public_url)
+        .bind(&user_id)
+        .execute(pool.as_ref())
+        .await;
+
+    match result {
+        Ok(_) => Ok(ImageUploadResult {
+            success: true,
+            url: Some(public_url),
+            error: None,
+        }),
+        Err(e) => Ok(ImageUploadResult {
+            success: false,
+            url: None,
+            error: Some(format!("Failed to update profile: {}", e)),
+        }),
+    }
+}
+
+/// Tauri command to remove the profile banner
+#[command]
+pub async fn delete_profile_banner(
+    session_store: State<'_, SessionStore>,
+) -> Result<ProfileResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    sqlx::query("UPDATE profiles SET banner_url = NULL WHERE user_id = $1")
+        .bind(&user_id)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let s3_client = create_s3_client().await;
+    let prefix = format!("banners/{}/", user_id);
+
+    let list_result = s3_client
+        .list_objects_v2()
+        .bucket(s3_bucket())
+        .prefix(&prefix)
+        .send()
+        .await;
 
-            Ok(ImageUploadResult {
+    match list_result {
+        Ok(output) => {
+            for obj in output.contents() {
+                if let Some(key) = obj.key() {
+                    let _ = s3_client
+                        .delete_object()
+                        .bucket(s3_bucket())
+                        .key(key)
+                        .send()
+                        .await;
+                }
+            }
+            Ok(ProfileResult {
                 success: true,
-                url: Some(public_url),
                 error: None,
             })
         }
-        Err(e) => Ok(ImageUploadResult {
+        Err(e) => Ok(ProfileResult {
             success: false,
-            url: None,
-            error: Some(format!("Failed to upload image: {}", e)),
+            error: Some(format!("Failed to delete banner: {}", e)),
         }),
     }
 }
@@ -177,7 +555,7 @@ pub async fn upload_profile_image(
 pub async fn delete_profile_image(
     session_store: State<'_, SessionStore>,
 ) -> Result<ProfileResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
 
     let s3_client = create_s3_client().await;
 
@@ -218,7 +596,7 @@ pub async fn delete_profile_image(
 /// Tauri command to check if user has a profile
 #[command]
 pub async fn check_profile_exists(session_store: State<'_, SessionStore>) -> Result<bool, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     let result: Option<(i32,)> = sqlx::query_as(
@@ -237,11 +615,12 @@ pub async fn check_profile_exists(session_store: State<'_, SessionStore>) -> Res
 pub async fn get_profile(
     session_store: State<'_, SessionStore>,
 ) -> Result<Option<ProfileData>, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     let profile: Option<ProfileData> = sqlx::query_as(
-        "SELECT username, nickname, avatar_url, status FROM profiles WHERE user_id = $1"
+        "SELECT username, nickname, avatar_url, status, bio, pronouns, banner_url, links, timezone
+         FROM profiles WHERE user_id = $1"
     )
     .bind(&user_id)
     .fetch_optional(pool.as_ref())
@@ -259,7 +638,7 @@ pub async fn create_profile(
     avatar_url: Option<String>,
     session_store: State<'_, SessionStore>,
 ) -> Result<ProfileResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Validate input
@@ -315,7 +694,7 @@ pub async fn get_profiles_by_ids(
     user_ids: Vec<String>,
     session_store: State<'_, SessionStore>,
 ) -> Result<Vec<ProfileNickname>, String> {
-    let _ = get_user_id_from_store(&session_store)?; // Verify authenticated
+    let viewer_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     if user_ids.is_empty() {
@@ -329,10 +708,14 @@ pub async fn get_profiles_by_ids(
         }
     }
 
+    // Status is only returned when the owner's privacy settings allow this viewer to see it
     let profiles: Vec<ProfileNickname> = sqlx::query_as(
-        "SELECT user_id, nickname, avatar_url, status FROM profiles WHERE user_id = ANY($1)"
+        "SELECT user_id, nickname, avatar_url,
+                CASE WHEN can_see_status(user_id, $2) THEN status END AS status
+         FROM profiles WHERE user_id = ANY($1)"
     )
     .bind(&user_ids)
+    .bind(&viewer_id)
     .fetch_all(pool.as_ref())
     .await
     .map_err(|e| format!("Database error: {}", e))?;
@@ -348,7 +731,7 @@ pub async fn update_profile(
     avatar_url: Option<String>,
     session_store: State<'_, SessionStore>,
 ) -> Result<ProfileResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Validate input
@@ -399,13 +782,174 @@ pub async fn update_profile(
     }
 }
 
+/// Tauri command to update bio, pronouns, external links and timezone
+#[command]
+pub async fn update_profile_details(
+    bio: Option<String>,
+    pronouns: Option<String>,
+    links: Vec<String>,
+    timezone: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<ProfileResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let bio = normalize_optional(bio);
+    let pronouns = normalize_optional(pronouns);
+    let timezone = normalize_optional(timezone);
+
+    if bio.as_ref().is_some_and(|b| b.chars().count() > MAX_BIO_LENGTH) {
+        return Ok(ProfileResult {
+            success: false,
+            error: Some(format!("Bio must be at most {} characters", MAX_BIO_LENGTH)),
+        });
+    }
+
+    if pronouns.as_ref().is_some_and(|p| p.chars().count() > MAX_PRONOUNS_LENGTH) {
+        return Ok(ProfileResult {
+            success: false,
+            error: Some(format!("Pronouns must be at most {} characters", MAX_PRONOUNS_LENGTH)),
+        });
+    }
+
+    // Only accept IANA zone names such as "Europe/Berlin"
+    if timezone.as_ref().is_some_and(|tz| tz.parse::<chrono_tz::Tz>().is_err()) {
+        return Ok(ProfileResult {
+            success: false,
+            error: Some("Invalid timezone".to_string()),
+        });
+    }
+
+    let links = match validate_links(links) {
+        Ok(links) => links,
+        Err(e) => {
+            return Ok(ProfileResult {
+                success: false,
+                error: Some(e),
+            });
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE profiles SET bio = $1, pronouns = $2, links = $3, timezone = $4 WHERE user_id = $5"
+    )
+    .bind(&bio)
+    .bind(&pronouns)
+    .bind(&links)
+    .bind(&timezone)
+    .bind(&user_id)
+    .execute(pool.as_ref())
+    .await;
+
+    match result {
+        Ok(_) => Ok(ProfileResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(ProfileResult {
+            success: false,
+            error: Some(format!("Failed to update profile: {}", e)),
+        }),
+    }
+}
+
+/// Tauri command to get another user's public profile card
+#[command]
+pub async fn get_user_profile(
+    user_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<Option<UserProfileCard>, String> {
+    let viewer_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    if uuid::Uuid::parse_str(&user_id).is_err() {
+        return Err(format!("Invalid user ID format: {}", user_id));
+    }
+
+    let profile: Option<UserProfileCard> = sqlx::query_as(
+        "SELECT user_id, username, nickname, avatar_url, banner_url,
+                CASE WHEN can_see_status(user_id, $2) THEN status END AS status,
+                bio, pronouns, links, timezone
+         FROM profiles WHERE user_id = $1"
+    )
+    .bind(&user_id)
+    .bind(&viewer_id)
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(profile)
+}
+
+/// Tauri command to search users by username or nickname.
+/// Exact matches rank first, then friends-of-friends, prefix matches, and fuzzy matches.
+#[command]
+pub async fn search_users(
+    query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<UserSearchResult>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let query = query.trim().to_lowercase();
+    let length = query.chars().count();
+    if !(MIN_SEARCH_LENGTH..=MAX_SEARCH_LENGTH).contains(&length) {
+        return Ok(vec![]);
+    }
+
+    let results: Vec<UserSearchResult> = sqlx::query_as(
+        r#"
+        SELECT user_id, username, nickname, avatar_url, status, is_friend, mutual_friend_count
+        FROM (
+            SELECT p.user_id, p.username, p.nickname, p.avatar_url,
+                   CASE WHEN can_see_status(p.user_id, $2) THEN p.status END AS status,
+                   EXISTS (
+                       SELECT 1 FROM friends f WHERE f.user_id = $2 AND f.friend_id = p.user_id
+                   ) AS is_friend,
+                   (SELECT COUNT(*) FROM friends f1
+                    JOIN friends f2 ON f2.user_id = f1.friend_id
+                    WHERE f1.user_id = $2 AND f2.friend_id = p.user_id) AS mutual_friend_count,
+                   (lower(p.username) = $1 OR lower(p.nickname) = $1) AS exact_match,
+                   (lower(p.username) LIKE $3 || '%' OR lower(p.nickname) LIKE $3 || '%') AS prefix_match,
+                   GREATEST(similarity(lower(p.username), $1),
+                            similarity(lower(p.nickname), $1)) AS score,
+                   COALESCE(ps.searchable, TRUE) AS searchable
+            FROM profiles p
+            LEFT JOIN privacy_settings ps ON ps.user_id = p.user_id
+            WHERE p.user_id <> $2
+              AND (lower(p.username) LIKE $3 || '%'
+                OR lower(p.nickname) LIKE $3 || '%'
+                OR lower(p.username) % $1
+                OR lower(p.nickname) % $1)
+              AND NOT is_blocked_between(p.user_id, $2)
+        ) matches
+        WHERE searchable OR is_friend
+        ORDER BY exact_match DESC, mutual_friend_count > 0 DESC, prefix_match DESC,
+                 score DESC, username
+        LIMIT $4 OFFSET $5
+        "#
+    )
+    .bind(&query)
+    .bind(&user_id)
+    .bind(escape_like(&query))
+    .bind(limit.unwrap_or(20).clamp(1, 50))
+    .bind(offset.unwrap_or(0).max(0))
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(results)
+}
+
 /// Tauri command to update user status
 #[command]
 pub async fn update_status(
     status: String,
     session_store: State<'_, SessionStore>,
 ) -> Result<ProfileResult, String> {
-    let user_id = get_user_id_from_store(&session_store)?;
+    let user_id = get_user_id_from_store(&session_store).await?;
     let pool = get_pool();
 
     // Validate status value
@@ -419,8 +963,9 @@ pub async fn update_status(
         });
     }
 
+    // Any explicit status change counts as activity
     let result = sqlx::query(
-        "UPDATE profiles SET status = $1 WHERE user_id = $2"
+        "UPDATE profiles SET status = $1, last_seen_at = NOW() WHERE user_id = $2"
     )
     .bind(&status)
     .bind(&user_id)
@@ -437,4 +982,45 @@ pub async fn update_status(
             error: Some(format!("Failed to update status: {}", e)),
         }),
     }
+}
+
+/// Set or clear the current user's custom status text
+#[command]
+pub async fn update_custom_status(
+    custom_status: Option<String>,
+    session_store: State<'_, SessionStore>,
+) -> Result<ProfileResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let pool = get_pool();
+
+    let custom_status = normalize_optional(custom_status);
+    if custom_status
+        .as_ref()
+        .is_some_and(|s| s.chars().count() > MAX_CUSTOM_STATUS_LENGTH)
+    {
+        return Ok(ProfileResult {
+            success: false,
+            error: Some(format!(
+                "Custom status must be at most {} characters",
+                MAX_CUSTOM_STATUS_LENGTH
+            )),
+        });
+    }
+
+    let result = sqlx::query("UPDATE profiles SET custom_status = $1 WHERE user_id = $2")
+        .bind(&custom_status)
+        .bind(&user_id)
+        .execute(pool.as_ref())
+        .await;
+
+    match result {
+        Ok(_) => Ok(ProfileResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(ProfileResult {
+            success: false,
+            error: Some(format!("Failed to update custom status: {}", e)),
+        }),
+    }
 }
\ No newline at end of file
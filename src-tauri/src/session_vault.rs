@@ -0,0 +1,387 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Leading byte of every stored blob, bumped if the layout changes
+const FORMAT_VERSION: u8 = 1;
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// AES-256 key length in bytes
+const KEY_LEN: usize = 32;
+
+/// File names used inside the app data directory
+const SESSION_FILE: &str = "session.bin";
+const KEY_FILE: &str = "session.key";
+
+/// OS keychain entry holding the vault key
+const KEYRING_SERVICE: &str = "cryptex.app.com";
+const KEYRING_ACCOUNT: &str = "session-key";
+
+// ============================================
+// TYPES
+// ============================================
+
+/// The part of a session worth keeping across restarts. Access and ID tokens are
+/// short-lived, so they are re-minted from the refresh token instead of being stored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PersistedSession {
+    pub refresh_token: String,
+    pub user_id: String,
+    pub email: String,
+    /// Missing from sessions saved before sessions were tracked
+    #[serde(default)]
+    pub session_id: String,
+}
+
+/// Storage for the encrypted session blob. The vault only ever hands backends
+/// ciphertext, so a backend can be a file, an OS keychain entry or an in-memory fake.
+pub trait SessionBackend: Send + Sync {
+    /// Read the stored blob, or `None` if nothing has been saved
+    fn read(&self) -> Result<Option<Vec<u8>>, String>;
+    fn write(&self, bytes: &[u8]) -> Result<(), String>;
+    fn delete(&self) -> Result<(), String>;
+}
+
+/// Keeps the blob in a single owner-only file
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+/// Keeps the blob in the OS credential store (macOS Keychain, Windows Credential
+/// Manager, Secret Service on Linux)
+pub struct KeyringBackend {
+    entry: keyring::Entry,
+}
+
+/// Encrypts sessions with AES-256-GCM before handing them to a backend
+pub struct SessionVault {
+    backend: Box<dyn SessionBackend>,
+    cipher: Aes256Gcm,
+}
+
+// ============================================
+// FILE BACKEND
+// ============================================
+
+/// Atomically write a file readable only by the current user
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp_path = path.with_extension("tmp");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&tmp_path)
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+impl FileBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl SessionBackend for FileBackend {
+    fn read(&self) -> Result<Option<Vec<u8>>, String> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", self.path.display(), e)),
+        }
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<(), String> {
+        write_private(&self.path, bytes)
+    }
+
+    fn delete(&self) -> Result<(), String> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to delete {}: {}", self.path.display(), e)),
+        }
+    }
+}
+
+// ============================================
+// KEYRING BACKEND
+// ============================================
+
+impl KeyringBackend {
+    pub fn new(service: &str, account: &str) -> Result<Self, String> {
+        keyring::Entry::new(service, account)
+            .map(|entry| Self { entry })
+            .map_err(|e| format!("Keychain unavailable: {}", e))
+    }
+}
+
+impl SessionBackend for KeyringBackend {
+    fn read(&self) -> Result<Option<Vec<u8>>, String> {
+        match self.entry.get_secret() {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+        }
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<(), String> {
+        self.entry
+            .set_secret(bytes)
+            .map_err(|e| format!("Failed to write to keychain: {}", e))
+    }
+
+    fn delete(&self) -> Result<(), String> {
+        match self.entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to delete from keychain: {}", e)),
+        }
+    }
+}
+
+// ============================================
+// VAULT
+// ============================================
+
+/// Read the vault key from `store`, creating a random one if it is missing or malformed
+fn load_or_create_key(store: &dyn SessionBackend) -> Result<[u8; KEY_LEN], String> {
+    if let Some(bytes) = store.read()? {
+        if let Ok(key) = <[u8; KEY_LEN]>::try_from(bytes.as_slice()) {
+            return Ok(key);
+        }
+    }
+
+    let key: [u8; KEY_LEN] = Aes256Gcm::generate_key(&mut OsRng).into();
+    store.write(&key)?;
+    Ok(key)
+}
+
+/// The vault key from the keychain. A key file left by the fallback is moved into the
+/// keychain so the session it protects survives, then removed from disk.
+fn load_keychain_key(keychain: &KeyringBackend, key_file: &FileBackend) -> Result<[u8; KEY_LEN], String> {
+    if keychain.read()?.is_none() {
+        if let Some(bytes) = key_file.read()? {
+            if bytes.len() == KEY_LEN {
+                keychain.write(&bytes)?;
+            }
+        }
+    }
+
+    let key = load_or_create_key(keychain)?;
+    key_file.delete()?;
+    Ok(key)
+}
+
+impl SessionVault {
+    pub fn new(backend: Box<dyn SessionBackend>, key: &[u8; KEY_LEN]) -> Self {
+        Self {
+            backend,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// File-backed vault in `dir`, keyed from the OS keychain.
+    ///
+    /// Where no keychain is available (e.g. a headless Linux machine without a Secret
+    /// Service), the key falls back to an owner-only file next to the session. That only
+    /// keeps the session from other users; anyone who can read the session file as this
+    /// user can read the key too.
+    pub fn open_in_dir(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let key_file = FileBackend::new(dir.join(KEY_FILE));
+        let key = match KeyringBackend::new(KEYRING_SERVICE, KEYRING_ACCOUNT)
+            .and_then(|keychain| load_keychain_key(&keychain, &key_file))
+        {
+            Ok(key) => key,
+            Err(e) => {
+                eprintln!("{}; falling back to a key file", e);
+                load_or_create_key(&key_file)?
+            }
+        };
+        let backend = FileBackend::new(dir.join(SESSION_FILE));
+        Ok(Self::new(Box::new(backend), &key))
+    }
+
+    /// Load the stored session. Anything unreadable (wrong key, corrupt or
+    /// tampered data) is discarded rather than surfaced.
+    pub fn load(&self) -> Option<PersistedSession> {
+        let blob = match self.backend.read() {
+            Ok(Some(blob)) => blob,
+            Ok(None) => return None,
+            Err(e) => {
+                eprintln!("Failed to read stored session: {}", e);
+                return None;
+            }
+        };
+
+        let session = match blob.split_first() {
+            Some((&FORMAT_VERSION, rest)) if rest.len() > NONCE_LEN => {
+                let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+                self.cipher
+                    .decrypt(Nonce::from_slice(nonce), ciphertext)
+                    .ok()
+                    .and_then(|json| serde_json::from_slice(&json).ok())
+            }
+            _ => None,
+        };
+
+        if session.is_none() {
+            eprintln!("Discarding unreadable stored session");
+            let _ = self.backend.delete();
+        }
+        session
+    }
+
+    pub fn save(&self, session: &PersistedSession) -> Result<(), String> {
+        let json = serde_json::to_vec(session)
+            .map_err(|e| format!("Failed to serialize session: {}", e))?;
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, json.as_slice())
+            .map_err(|e| format!("Failed to encrypt session: {}", e))?;
+
+        let mut blob = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        blob.push(FORMAT_VERSION);
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+
+        self.backend.write(&blob)
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        self.backend.delete()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// In-memory backend whose stored blob the test can inspect and modify
+    #[derive(Clone, Default)]
+    struct MemoryBackend(Arc<Mutex<Option<Vec<u8>>>>);
+
+    impl SessionBackend for MemoryBackend {
+        fn read(&self) -> Result<Option<Vec<u8>>, String> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn write(&self, bytes: &[u8]) -> Result<(), String> {
+            *self.0.lock().unwrap() = Some(bytes.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self) -> Result<(), String> {
+            *self.0.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    fn session() -> PersistedSession {
+        PersistedSession {
+            refresh_token: "refresh-token-value".to_string(),
+            user_id: "0b7c5e2e-4d1a-4f3e-9c55-2a6f0d9e8b13".to_string(),
+            email: "user@example.com".to_string(),
+            session_id: "6f1d2c3b-8a9e-4b7c-a1d2-3e4f5a6b7c8d".to_string(),
+        }
+    }
+
+    fn vault(backend: &MemoryBackend, key: [u8; KEY_LEN]) -> SessionVault {
+        SessionVault::new(Box::new(backend.clone()), &key)
+    }
+
+    #[test]
+    fn round_trips_a_session() {
+        let backend = MemoryBackend::default();
+        let vault = vault(&backend, [7; KEY_LEN]);
+
+        assert_eq!(vault.load(), None);
+        vault.save(&session()).unwrap();
+        assert_eq!(vault.load(), Some(session()));
+
+        vault.clear().unwrap();
+        assert_eq!(vault.load(), None);
+    }
+
+    #[test]
+    fn stores_only_ciphertext() {
+        let backend = MemoryBackend::default();
+        vault(&backend, [7; KEY_LEN]).save(&session()).unwrap();
+
+        let blob = backend.read().unwrap().unwrap();
+        assert_eq!(blob[0], FORMAT_VERSION);
+        for secret in ["refresh-token-value", "user@example.com"] {
+            assert!(!blob.windows(secret.len()).any(|w| w == secret.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn discards_a_tampered_blob() {
+        let backend = MemoryBackend::default();
+        let vault = vault(&backend, [7; KEY_LEN]);
+        vault.save(&session()).unwrap();
+
+        let mut blob = backend.read().unwrap().unwrap();
+        let last = blob.len() - 1;
+        blob[last] ^= 0x01;
+        backend.write(&blob).unwrap();
+
+        assert_eq!(vault.load(), None);
+        assert_eq!(backend.read().unwrap(), None, "a blob failing GCM should be deleted");
+    }
+
+    #[test]
+    fn discards_a_blob_under_the_wrong_key() {
+        let backend = MemoryBackend::default();
+        vault(&backend, [7; KEY_LEN]).save(&session()).unwrap();
+
+        assert_eq!(vault(&backend, [8; KEY_LEN]).load(), None);
+        assert_eq!(backend.read().unwrap(), None);
+    }
+
+    #[test]
+    fn discards_an_unknown_format() {
+        let backend = MemoryBackend::default();
+        let vault = vault(&backend, [7; KEY_LEN]);
+        vault.save(&session()).unwrap();
+
+        let mut blob = backend.read().unwrap().unwrap();
+        blob[0] = FORMAT_VERSION + 1;
+        backend.write(&blob).unwrap();
+        assert_eq!(vault.load(), None);
+
+        backend.write(&[FORMAT_VERSION, 1, 2, 3]).unwrap();
+        assert_eq!(vault.load(), None);
+    }
+
+    #[test]
+    fn reuses_a_stored_key_and_replaces_a_malformed_one() {
+        let store = MemoryBackend::default();
+        let key = load_or_create_key(&store).unwrap();
+        assert_eq!(load_or_create_key(&store).unwrap(), key);
+
+        store.write(b"too short").unwrap();
+        let replaced = load_or_create_key(&store).unwrap();
+        assert_eq!(store.read().unwrap(), Some(replaced.to_vec()));
+    }
+}
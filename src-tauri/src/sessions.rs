@@ -0,0 +1,315 @@
+use crate::account_api;
+use crate::auth::{
+    create_cognito_client, end_local_session, get_access_token_from_store,
+    get_user_id_from_store, SessionStore,
+};
+use crate::db::get_pool;
+use aws_sdk_cognitoidentityprovider::primitives::{DateTime, DateTimeFormat};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use tauri::{command, State};
+use uuid::Uuid;
+
+/// Remembered devices returned by Cognito per page (its maximum)
+const DEVICE_PAGE_SIZE: i32 = 60;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// A signed-in session of the current user
+#[derive(Serialize, FromRow)]
+pub struct ActiveSession {
+    pub id: String,
+    pub platform: String,
+    pub created_at: String,
+    pub last_seen_at: String,
+    /// Whether this is the session making the request
+    pub is_current: bool,
+}
+
+#[derive(Serialize)]
+pub struct ActiveSessionsResult {
+    pub success: bool,
+    pub sessions: Vec<ActiveSession>,
+    pub error: Option<String>,
+}
+
+/// A device Cognito remembers for the current user
+#[derive(Serialize)]
+pub struct RememberedDevice {
+    pub device_key: String,
+    pub name: Option<String>,
+    pub created_at: Option<String>,
+    pub last_authenticated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RememberedDevicesResult {
+    pub success: bool,
+    pub devices: Vec<RememberedDevice>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NewSession {
+    platform: String,
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct RegisteredSession {
+    id: String,
+}
+
+#[derive(Serialize)]
+pub struct SessionResult {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+/// Operating system and architecture of this install, e.g. `macos (aarch64)`
+fn current_platform() -> String {
+    format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn format_device_date(date: Option<&DateTime>) -> Option<String> {
+    date.and_then(|date| date.fmt(DateTimeFormat::DateTime).ok())
+}
+
+/// Register a new sign-in on this device with the account API and return its session id.
+/// The API keeps the refresh token (outside the shared database) so that revoking the
+/// session from another device can revoke the token with Cognito.
+pub(crate) async fn register_session(access_token: &str, refresh_token: &str) -> Result<String, String> {
+    let registered: RegisteredSession = account_api::request_json(
+        Method::POST,
+        "/sessions",
+        access_token,
+        Some(&NewSession {
+            platform: current_platform(),
+            refresh_token: refresh_token.to_string(),
+        }),
+    )
+    .await?;
+
+    Ok(registered.id)
+}
+
+/// Mark a session as still in use. Returns false if it has been revoked.
+pub(crate) async fn touch_session(session_id: &str) -> Result<bool, String> {
+    let Ok(session_id) = Uuid::parse_str(session_id) else {
+        return Ok(false);
+    };
+    let pool = get_pool();
+
+    let touched = sqlx::query(
+        "UPDATE auth_sessions SET last_seen_at = NOW()
+         WHERE id = $1 AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(touched.rows_affected() > 0)
+}
+
+/// Mark one session, or every session of `user_id` when `session_id` is None, as revoked
+pub(crate) async fn revoke_sessions(user_id: &str, session_id: Option<&str>) -> Result<u64, String> {
+    let session_id = match session_id {
+        Some(id) => Some(Uuid::parse_str(id).map_err(|_| "Invalid session id".to_string())?),
+        None => None,
+    };
+    let pool = get_pool();
+
+    let revoked = sqlx::query(
+        "UPDATE auth_sessions SET revoked_at = NOW()
+         WHERE user_id = $1 AND ($2::uuid IS NULL OR id = $2) AND revoked_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(revoked.rows_affected())
+}
+
+// ============================================
+// SESSION COMMANDS
+// ============================================
+
+/// List the current user's signed-in sessions, most recently used first
+#[command]
+pub async fn get_active_sessions(
+    session_store: State<'_, SessionStore>,
+) -> Result<ActiveSessionsResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let current_id = session_store.current_session_id();
+    let pool = get_pool();
+
+    let sessions: Vec<ActiveSession> = sqlx::query_as(
+        "SELECT id::text AS id, platform, created_at::text AS created_at,
+                last_seen_at::text AS last_seen_at, id::text = $2 AS is_current
+         FROM auth_sessions
+         WHERE user_id = $1 AND revoked_at IS NULL
+         ORDER BY last_seen_at DESC"
+    )
+    .bind(&user_id)
+    .bind(&current_id)
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(ActiveSessionsResult {
+        success: true,
+        sessions,
+        error: None,
+    })
+}
+
+/// Revoke one of the current user's sessions. For another device, the account API revokes
+/// that device's refresh token with Cognito, so it can't mint new access tokens, and the app
+/// stops acting for the session on its next command. Revoking this device's session signs
+/// out right away.
+#[command]
+pub async fn revoke_session(
+    session_id: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<SessionResult, String> {
+    get_user_id_from_store(&session_store).await?;
+
+    if session_store.current_session_id() == session_id {
+        end_local_session(&session_store).await?;
+        return Ok(SessionResult {
+            success: true,
+            error: None,
+        });
+    }
+
+    let not_found = || SessionResult {
+        success: false,
+        error: Some("Session not found".to_string()),
+    };
+    let Ok(session_id) = Uuid::parse_str(&session_id) else {
+        return Ok(not_found());
+    };
+
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let response = account_api::send(
+        Method::DELETE,
+        &format!("/sessions/{}", session_id),
+        &access_token,
+        None::<&()>,
+    )
+    .await?;
+
+    match response.status() {
+        status if status.is_success() => Ok(SessionResult {
+            success: true,
+            error: None,
+        }),
+        StatusCode::NOT_FOUND => Ok(not_found()),
+        _ => Ok(SessionResult {
+            success: false,
+            error: Some(account_api::error_message(response).await),
+        }),
+    }
+}
+
+// ============================================
+// DEVICE COMMANDS
+// ============================================
+
+/// List the devices Cognito remembers for the current user
+#[command]
+pub async fn get_remembered_devices(
+    session_store: State<'_, SessionStore>,
+) -> Result<RememberedDevicesResult, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let mut devices = Vec::new();
+    let mut pagination_token = None;
+    loop {
+        let response = match client
+            .list_devices()
+            .access_token(&access_token)
+            .limit(DEVICE_PAGE_SIZE)
+            .set_pagination_token(pagination_token)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(RememberedDevicesResult {
+                    success: false,
+                    devices: Vec::new(),
+                    error: Some(format!("Failed to load devices: {:?}", e.into_service_error())),
+                });
+            }
+        };
+
+        devices.extend(response.devices().iter().map(|device| RememberedDevice {
+            device_key: device.device_key().unwrap_or_default().to_string(),
+            name: device
+                .device_attributes()
+                .iter()
+                .find(|attribute| attribute.name() == "device_name")
+                .and_then(|attribute| attribute.value())
+                .map(str::to_string),
+            created_at: format_device_date(device.device_create_date()),
+            last_authenticated_at: format_device_date(device.device_last_authenticated_date()),
+        }));
+
+        pagination_token = response.pagination_token().map(str::to_string);
+        if pagination_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(RememberedDevicesResult {
+        success: true,
+        devices,
+        error: None,
+    })
+}
+
+/// Stop remembering a device, so signing in from it again goes through MFA
+#[command]
+pub async fn forget_device(
+    device_key: String,
+    session_store: State<'_, SessionStore>,
+) -> Result<SessionResult, String> {
+    let access_token = get_access_token_from_store(&session_store).await?;
+    let client = create_cognito_client().await;
+
+    let result = client
+        .forget_device()
+        .access_token(access_token)
+        .device_key(&device_key)
+        .send()
+        .await;
+
+    match result {
+        Ok(_) => Ok(SessionResult {
+            success: true,
+            error: None,
+        }),
+        Err(e) => {
+            let error_message = match e.into_service_error() {
+                err if err.is_resource_not_found_exception() => "Device not found".to_string(),
+                err => format!("Failed to forget device: {:?}", err),
+            };
+            Ok(SessionResult {
+                success: false,
+                error: Some(error_message),
+            })
+        }
+    }
+}
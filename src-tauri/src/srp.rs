@@ -0,0 +1,321 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The 3072-bit group from RFC 5054 that Cognito uses, with generator 2
+const N_HEX: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33",
+    "A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7",
+    "ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864",
+    "D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2",
+    "08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A93AD2CAFFFFFFFFFFFFFFFF",
+);
+const G: u32 = 2;
+
+/// Bytes of randomness in the client's ephemeral secret `a`
+const EPHEMERAL_SECRET_LEN: usize = 128;
+
+/// HKDF info string Cognito uses for the password-claim key
+const DERIVED_KEY_INFO: &[u8] = b"Caldera Derived Key";
+const DERIVED_KEY_LEN: usize = 16;
+
+// ============================================
+// HELPERS
+// ============================================
+
+fn n() -> BigUint {
+    BigUint::parse_bytes(N_HEX.as_bytes(), 16).expect("SRP group prime is valid hex")
+}
+
+/// Big-endian bytes of `value` with a leading zero byte whenever the top bit is set,
+/// matching how Cognito's SDKs pad values before hashing them
+fn pad(value: &BigUint) -> Vec<u8> {
+    let mut bytes = value.to_bytes_be();
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+    bytes
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn hash_to_int(parts: &[&[u8]]) -> BigUint {
+    BigUint::from_bytes_be(&hash(parts))
+}
+
+fn parse_hex(name: &str, hex: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(hex.as_bytes(), 16).ok_or_else(|| format!("Invalid {} from server", name))
+}
+
+/// Cognito's `TIMESTAMP` format, e.g. `Tue Mar 5 08:03:01 UTC 2024` (day not zero-padded)
+pub fn srp_timestamp(now: DateTime<Utc>) -> String {
+    now.format("%a %b %-d %H:%M:%S UTC %Y").to_string()
+}
+
+// ============================================
+// CLIENT
+// ============================================
+
+/// Client side of Cognito's SRP-6a sign-in (`USER_SRP_AUTH` then `PASSWORD_VERIFIER`).
+/// The password never leaves the device; only a proof derived from it is sent.
+pub struct SrpClient {
+    /// The part of the user pool id after the region, e.g. `abc123` in `us-east-1_abc123`
+    pool_name: String,
+    a: BigUint,
+    big_a: BigUint,
+}
+
+impl SrpClient {
+    /// New client with a random ephemeral secret
+    pub fn new(user_pool_id: &str) -> Self {
+        let mut a = [0u8; EPHEMERAL_SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut a);
+        Self::with_secret(user_pool_id, BigUint::from_bytes_be(&a))
+    }
+
+    /// Client with a fixed ephemeral secret `a`
+    pub fn with_secret(user_pool_id: &str, a: BigUint) -> Self {
+        let pool_name = user_pool_id
+            .split_once('_')
+            .map(|(_, name)| name)
+            .unwrap_or(user_pool_id)
+            .to_string();
+        let big_a = BigUint::from(G).modpow(&a, &n());
+        Self {
+            pool_name,
+            a,
+            big_a,
+        }
+    }
+
+    /// `SRP_A` auth parameter for `initiate_auth`
+    pub fn public_value_hex(&self) -> String {
+        self.big_a.to_str_radix(16)
+    }
+
+    /// 16-byte key both sides derive from the shared secret
+    fn password_authentication_key(
+        &self,
+        user_id: &str,
+        password: &str,
+        salt_hex: &str,
+        srp_b_hex: &str,
+    ) -> Result<[u8; DERIVED_KEY_LEN], String> {
+        let n = n();
+        let g = BigUint::from(G);
+        let salt = parse_hex("salt", salt_hex)?;
+        let big_b = parse_hex("SRP_B", srp_b_hex)?;
+
+        if (&big_b % &n) == BigUint::ZERO {
+            return Err("Invalid SRP_B from server".to_string());
+        }
+
+        let k = hash_to_int(&[&pad(&n), &pad(&g)]);
+        let u = hash_to_int(&[&pad(&self.big_a), &pad(&big_b)]);
+        if u == BigUint::ZERO {
+            return Err("Invalid SRP_B from server".to_string());
+        }
+
+        let identity = hash(&[self.pool_name.as_bytes(), user_id.as_bytes(), b":", password.as_bytes()]);
+        let x = hash_to_int(&[&pad(&salt), &identity]);
+
+        // S = (B - k * g^x) ^ (a + u * x) mod N, kept non-negative by adding k * N first
+        let kgx = (&k * g.modpow(&x, &n)) % &n;
+        let base = (&big_b + &k * &n - kgx) % &n;
+        let s = base.modpow(&(&self.a + &u * &x), &n);
+
+        let mut key = [0u8; DERIVED_KEY_LEN];
+        Hkdf::<Sha256>::new(Some(&pad(&u)), &pad(&s))
+            .expand(DERIVED_KEY_INFO, &mut key)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+
+    /// `PASSWORD_CLAIM_SIGNATURE` for the `PASSWORD_VERIFIER` challenge
+    pub fn password_claim_signature(
+        &self,
+        user_id: &str,
+        password: &str,
+        salt_hex: &str,
+        srp_b_hex: &str,
+        secret_block: &str,
+        timestamp: &str,
+    ) -> Result<String, String> {
+        let key = self.password_authentication_key(user_id, password, salt_hex, srp_b_hex)?;
+        let secret_block = STANDARD
+            .decode(secret_block)
+            .map_err(|_| "Invalid secret block from server".to_string())?;
+
+        let mut mac = HmacSha256::new_from_slice(&key).map_err(|e| e.to_string())?;
+        mac.update(self.pool_name.as_bytes());
+        mac.update(user_id.as_bytes());
+        mac.update(&secret_block);
+        mac.update(timestamp.as_bytes());
+        Ok(STANDARD.encode(mac.finalize().into_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Fixed-input vectors. The expected values were produced with an independent port of
+    // the cognito_srp/warrant key derivation and cross-checked against the server side of
+    // the exchange (B = k*v + g^b, S = (A * v^u)^b), so they pin the wire format rather
+    // than just this implementation.
+    const POOL_ID: &str = "us-east-1_AbC123";
+    const USER_ID: &str = "7d1e5c2a-9b3f-4e8d-a6c0-1f2e3d4c5b6a";
+    const PASSWORD: &str = "correct horse battery staple";
+    const A_SECRET: &str = "7b4f2c8e19a05d36e4c1b7f0928d3a6e5f1c0b9a8d7e6f5041322314f5e6d7c8";
+    const B_SECRET: &str = "3c9d2e7f10a4b592";
+    const SALT: &str = "e5a1b29c03d4f8e7161a2b3c4d5e6f70";
+    const SECRET_BLOCK: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4v";
+    const TIMESTAMP: &str = "Tue Mar 5 08:03:01 UTC 2024";
+
+    const SRP_A: &str = concat!(
+        "23863d48b482f7100fa202f694e6c2c376beae5d6a305b172be24a5fe122e7051b737081293d44ad8a1fe5a52db51798",
+        "f1b7b7c65f01d1bc1e30002fc5b321f386bce25dac092ef26ab3e1a07bdefc6b3c7db925573c9de552f75910099e9fb7",
+        "a41b1fe46f1ff4eadb78460a586fbd31801737c71db97e34705e69cea5990500bd334b8d8c534e52215b969a89700cbc",
+        "7a48798610e07e656ad7208be42295950f0d546714efc0e646dc424e67430cc0490b096f82b3169dd381e8604a6e7545",
+        "375acd56ffe3387b617f22736d9f7b3e19da52374b01d9871c1b81b9d81f618e98a45eabee260b285e64ea5ab2359ae6",
+        "f6d2c96c12c1336dd805d1962a423f4cf8a41fff3a4abc766df4be506e4ffe02241631e79037541c385a0b912bb30b71",
+        "2dcc6e308cd5149679122ac7974523fc75ee03be80636575a08e86d4c456ffb3e517a96c7b52c3f660de37786f0127f1",
+        "47125a7496fa1b5640cd6d7243f5bc29a6100cea9581380e305c072aa2face40b5a3bdb2fe1ea6c4a3c9c24373e524c9",
+    );
+    const SRP_B: &str = concat!(
+        "91f99a8d9f1fd2f268fd8d5baa96961f225eb66ebf30653d5ca3f71ff08fa57f992759febff5eb1732471e6111105388",
+        "b59d9c23e9de80834f30c35edc6d95a64e30ff72834a3c8182e0095ce1ba5173343bd239d6e5285f988a150075119a64",
+        "7913472d20b8592f306efcfa6d499c79009bb05b2a2832871fb7d665fc46bd6729fb2a7d4e06198eb7d0e0a2b931a3df",
+        "34483dc782be38d97adcb2acd8c5183f729c7d50243b8dc4b533587607916ac6ffd6d3bcceb4fe51d85a9e4eaffc8cb8",
+        "033bcc7198097cec5446fe9f40ac8e0fa0c10d34070dec8068152188c1262480740f1b71a0881af2e16c024de08b5573",
+        "97cccdd04721890f4467ce14de64cdd4a898775d311e605fa51108e83797c00cfe7bd4c6bc618030b021d7cd7bb0f2f8",
+        "2fe21919b044a5ca138d6c91bcb5d6da8020627c8d1ad41b41374d079a672fcb27cd0b21c0f3bc14ce42b00761107c24",
+        "e574839be60008e47416f0707626ca6ca90b4ffb23ae5937b54ba89434d2f4f26decb8bcf9028b242600f4a2a4c0b94c",
+    );
+    const K: &str = "538282c4354742d7cbbde2359fcf67f9f5b3a6b08791e5011b43b8a5b66d9ee6";
+    const U: &str = "12d132fb02970e045c87f1dd967b1b1eedb91ca1441247d9c7c7baf45da4430b";
+    const X: &str = "e83673c4be501d689511054f5c543b4c66f36340c875afc23a7c36f3de5ae7fa";
+    const KEY: &str = "ff5ccee14395aeb06d40640abdb0b64b";
+    const SIGNATURE: &str = "lHZ2GJ7sHF0+7QktdGsMQzzGpiLllBybpmgrNSMdiNM=";
+
+    fn int(hex: &str) -> BigUint {
+        BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+    }
+
+    fn client() -> SrpClient {
+        SrpClient::with_secret(POOL_ID, int(A_SECRET))
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn public_value_matches_vector() {
+        assert_eq!(client().public_value_hex(), SRP_A);
+        assert_eq!(SrpClient::with_secret(POOL_ID, BigUint::from(1u32)).public_value_hex(), "2");
+    }
+
+    #[test]
+    fn pool_name_drops_region() {
+        assert_eq!(client().pool_name, "AbC123");
+    }
+
+    #[test]
+    fn intermediate_values_match_vector() {
+        let identity = hash(&[b"AbC123", USER_ID.as_bytes(), b":", PASSWORD.as_bytes()]);
+        assert_eq!(hash_to_int(&[&pad(&n()), &pad(&BigUint::from(G))]), int(K));
+        assert_eq!(hash_to_int(&[&pad(&int(SRP_A)), &pad(&int(SRP_B))]), int(U));
+        assert_eq!(hash_to_int(&[&pad(&int(SALT)), &identity]), int(X));
+    }
+
+    #[test]
+    fn server_b_is_consistent_with_vector() {
+        let n = n();
+        let g = BigUint::from(G);
+        let v = g.modpow(&int(X), &n);
+        assert_eq!((int(K) * &v + g.modpow(&int(B_SECRET), &n)) % &n, int(SRP_B));
+    }
+
+    #[test]
+    fn password_authentication_key_matches_vector() {
+        let key = client().password_authentication_key(USER_ID, PASSWORD, SALT, SRP_B).unwrap();
+        assert_eq!(to_hex(&key), KEY);
+    }
+
+    #[test]
+    fn password_claim_signature_matches_vector() {
+        let signature = client()
+            .password_claim_signature(USER_ID, PASSWORD, SALT, SRP_B, SECRET_BLOCK, TIMESTAMP)
+            .unwrap();
+        assert_eq!(signature, SIGNATURE);
+    }
+
+    #[test]
+    fn signature_changes_with_password() {
+        let signature = client()
+            .password_claim_signature(USER_ID, "wrong password", SALT, SRP_B, SECRET_BLOCK, TIMESTAMP)
+            .unwrap();
+        assert_ne!(signature, SIGNATURE);
+    }
+
+    #[test]
+    fn pad_adds_zero_byte_only_when_high_bit_set() {
+        assert_eq!(pad(&BigUint::from(0x80u32)), vec![0x00, 0x80]);
+        assert_eq!(pad(&BigUint::from(0xff01u32)), vec![0x00, 0xff, 0x01]);
+        assert_eq!(pad(&BigUint::from(0x7fu32)), vec![0x7f]);
+        assert_eq!(pad(&BigUint::from(0x0100u32)), vec![0x01, 0x00]);
+        assert_eq!(pad(&int(SALT)).len(), 17);
+        assert_eq!(pad(&n()).len(), 385);
+    }
+
+    #[test]
+    fn timestamp_does_not_pad_day() {
+        let single = Utc.with_ymd_and_hms(2024, 3, 5, 8, 3, 1).unwrap();
+        let double = Utc.with_ymd_and_hms(2024, 12, 25, 23, 59, 9).unwrap();
+        assert_eq!(srp_timestamp(single), "Tue Mar 5 08:03:01 UTC 2024");
+        assert_eq!(srp_timestamp(double), "Wed Dec 25 23:59:09 UTC 2024");
+    }
+
+    #[test]
+    fn rejects_b_that_is_a_multiple_of_n() {
+        let client = client();
+        for multiple in [BigUint::ZERO, n(), n() * 2u32] {
+            let b_hex = multiple.to_str_radix(16);
+            assert_eq!(
+                client.password_authentication_key(USER_ID, PASSWORD, SALT, &b_hex),
+                Err("Invalid SRP_B from server".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_server_values() {
+        let client = client();
+        assert!(client.password_authentication_key(USER_ID, PASSWORD, "not-hex", SRP_B).is_err());
+        assert!(client.password_authentication_key(USER_ID, PASSWORD, SALT, "zz").is_err());
+        assert!(client
+            .password_claim_signature(USER_ID, PASSWORD, SALT, SRP_B, "%%%", TIMESTAMP)
+            .is_err());
+    }
+}
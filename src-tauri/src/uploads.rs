@@ -0,0 +1,597 @@
+use crate::auth::{get_user_id_from_store, SessionStore};
+use crate::config::{cloudfront_url, s3_bucket};
+use crate::profile::create_s3_client;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client as S3Client;
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+// ============================================
+// CONSTANTS
+// ============================================
+
+/// Size of each uploaded chunk (S3 requires at least 5MB for every part but the last)
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Largest file accepted for upload (2GB)
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// How many times a single part is attempted before the upload is paused
+const MAX_PART_ATTEMPTS: u32 = 5;
+
+/// Event emitted to the frontend as parts complete
+const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+
+// ============================================
+// TYPES
+// ============================================
+
+/// A part that S3 has acknowledged
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadedPart {
+    pub part_number: i32,
+    pub e_tag: String,
+}
+
+/// Progress of a multipart upload, persisted to disk after every part
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadState {
+    pub upload_id: String,
+    pub user_id: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub modified_at: i64,
+    pub key: String,
+    pub s3_upload_id: String,
+    pub content_type: String,
+    pub completed_parts: Vec<UploadedPart>,
+}
+
+/// Progress event payload sent to the frontend
+#[derive(Serialize, Clone)]
+pub struct UploadProgress {
+    pub upload_id: String,
+    pub bytes_uploaded: u64,
+    pub total_bytes: u64,
+    pub completed: bool,
+}
+
+/// Upload that was interrupted and can be resumed
+#[derive(Serialize)]
+pub struct PendingUpload {
+    pub upload_id: String,
+    pub file_name: String,
+    pub bytes_uploaded: u64,
+    pub total_bytes: u64,
+}
+
+/// Result for file upload operations
+#[derive(Serialize)]
+pub struct FileUploadResult {
+    pub success: bool,
+    pub upload_id: Option<String>,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+// ============================================
+// HELPER FUNCTIONS
+// ============================================
+
+/// Directory holding the persisted state of unfinished uploads
+fn uploads_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    Ok(dir.join("uploads"))
+}
+
+fn state_path(app: &AppHandle, upload_id: &str) -> Result<PathBuf, String> {
+    // Upload IDs become file names, so only accept our own UUIDs
+    if uuid::Uuid::parse_str(upload_id).is_err() {
+        return Err("Invalid upload ID".to_string());
+    }
+    Ok(uploads_dir(app)?.join(format!("{}.json", upload_id)))
+}
+
+/// Write the upload state atomically so a crash never leaves a torn file
+async fn save_state(app: &AppHandle, state: &UploadState) -> Result<(), String> {
+    let dir = uploads_dir(app)?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create uploads directory: {}", e))?;
+
+    let path = state_path(app, &state.upload_id)?;
+    let tmp_path = path.with_extension("json.tmp");
+    let json = serde_json::to_vec(state).map_err(|e| e.to_string())?;
+
+    tokio::fs::write(&tmp_path, json)
+        .await
+        .map_err(|e| format!("Failed to save upload progress: {}", e))?;
+    tokio::fs::rename(&tmp_path, &path)
+        .await
+        .map_err(|e| format!("Failed to save upload progress: {}", e))?;
+
+    Ok(())
+}
+
+async fn load_state(app: &AppHandle, upload_id: &str) -> Result<UploadState, String> {
+    let path = state_path(app, upload_id)?;
+    let json = tokio::fs::read(&path)
+        .await
+        .map_err(|_| "Upload not found".to_string())?;
+    serde_json::from_slice(&json).map_err(|e| format!("Corrupt upload state: {}", e))
+}
+
+async fn remove_state(app: &AppHandle, upload_id: &str) {
+    if let Ok(path) = state_path(app, upload_id) {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
+
+/// Size and last-modified time (ms) used to detect a file changing between resumes
+async fn file_fingerprint(path: &Path) -> Result<(u64, i64), String> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| format!("Cannot read file: {}", e))?;
+
+    if !metadata.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let modified_at = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    Ok((metadata.len(), modified_at))
+}
+
+/// Keep only characters that are safe inside an S3 key
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+
+    if cleaned.is_empty() {
+        "file".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn content_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn part_count(file_size: u64) -> i32 {
+    file_size.div_ceil(PART_SIZE) as i32
+}
+
+fn part_len(file_size: u64, part_number: i32) -> u64 {
+    let offset = (part_number as u64 - 1) * PART_SIZE;
+    PART_SIZE.min(file_size - offset)
+}
+
+fn bytes_uploaded(state: &UploadState) -> u64 {
+    state
+        .completed_parts
+        .iter()
+        .map(|p| part_len(state.file_size, p.part_number))
+        .sum()
+}
+
+fn emit_progress(app: &AppHandle, state: &UploadState, completed: bool) {
+    let _ = app.emit(
+        UPLOAD_PROGRESS_EVENT,
+        UploadProgress {
+            upload_id: state.upload_id.clone(),
+            bytes_uploaded: bytes_uploaded(state),
+            total_bytes: state.file_size,
+            completed,
+        },
+    );
+}
+
+/// Whether an S3 call is worth retrying: the request never got an answer, or S3 was
+/// overloaded or failed internally. Errors like `NoSuchUpload` or `AccessDenied` won't
+/// go away on their own.
+fn is_transient<E: ProvideErrorMetadata>(error: &SdkError<E, HttpResponse>) -> bool {
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => true,
+        SdkError::ServiceError(service_error) => {
+            let status = service_error.raw().status();
+            status.is_server_error()
+                || status.as_u16() == 429
+                || matches!(
+                    service_error.err().code(),
+                    Some("SlowDown" | "RequestTimeout" | "Throttling" | "ThrottlingException")
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Upload one part, retrying transient failures with exponential backoff
+async fn upload_part_with_retry(
+    s3_client: &S3Client,
+    state: &UploadState,
+    part_number: i32,
+    data: Vec<u8>,
+) -> Result<String, String> {
+    let mut last_error = String::new();
+
+    for attempt in 0..MAX_PART_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt))).await;
+        }
+
+        let result = s3_client
+            .upload_part()
+            .bucket(s3_bucket())
+            .key(&state.key)
+            .upload_id(&state.s3_upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data.clone()))
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => {
+                return output
+                    .e_tag()
+                    .map(|tag| tag.to_string())
+                    .ok_or_else(|| "Upload part returned no ETag".to_string());
+            }
+            Err(e) if is_transient(&e) => last_error = DisplayErrorContext(&e).to_string(),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to upload part {}: {}",
+                    part_number,
+                    DisplayErrorContext(&e)
+                ));
+            }
+        }
+    }
+
+    Err(format!(
+        "Failed to upload part {} after {} attempts: {}",
+        part_number, MAX_PART_ATTEMPTS, last_error
+    ))
+}
+
+/// Upload every part that isn't done yet, then complete the multipart upload
+async fn run_upload(app: &AppHandle, mut state: UploadState) -> Result<String, String> {
+    let s3_client = create_s3_client().await;
+
+    let mut file = tokio::fs::File::open(&state.file_path)
+        .await
+        .map_err(|e| format!("Cannot open file: {}", e))?;
+
+    emit_progress(app, &state, false);
+
+    for part_number in 1..=part_count(state.file_size) {
+        if state.completed_parts.iter().any(|p| p.part_number == part_number) {
+            continue;
+        }
+
+        let offset = (part_number as u64 - 1) * PART_SIZE;
+        let mut data = vec![0u8; part_len(state.file_size, part_number) as usize];
+
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        file.read_exact(&mut data)
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let e_tag = upload_part_with_retry(&s3_client, &state, part_number, data).await?;
+
+        state.completed_parts.push(UploadedPart { part_number, e_tag });
+        save_state(app, &state).await?;
+        emit_progress(app, &state, false);
+    }
+
+    state.completed_parts.sort_by_key(|p| p.part_number);
+    let parts: Vec<CompletedPart> = state
+        .completed_parts
+        .iter()
+        .map(|p| {
+            CompletedPart::builder()
+                .part_number(p.part_number)
+                .e_tag(&p.e_tag)
+                .build()
+        })
+        .collect();
+
+    s3_client
+        .complete_multipart_upload()
+        .bucket(s3_bucket())
+        .key(&state.key)
+        .upload_id(&state.s3_upload_id)
+        .multipart_upload(
+            CompletedMultipartUpload::builder()
+                .set_parts(Some(parts))
+                .build(),
+        )
+        .send()
+        .await
+        .map_err(|e| format!("Failed to complete upload: {}", e))?;
+
+    remove_state(app, &state.upload_id).await;
+    emit_progress(app, &state, true);
+
+    Ok(format!("{}/{}", cloudfront_url(), state.key))
+}
+
+fn upload_result(upload_id: String, result: Result<String, String>) -> FileUploadResult {
+    match result {
+        Ok(url) => FileUploadResult {
+            success: true,
+            upload_id: Some(upload_id),
+            url: Some(url),
+            error: None,
+        },
+        // The upload ID is still returned so the frontend can offer a resume
+        Err(e) => FileUploadResult {
+            success: false,
+            upload_id: Some(upload_id),
+            url: None,
+            error: Some(e),
+        },
+    }
+}
+
+// ============================================
+// UPLOAD COMMANDS
+// ============================================
+
+/// Start a chunked upload of a local file to S3
+#[command]
+pub async fn start_file_upload(
+    file_path: String,
+    app: AppHandle,
+    session_store: State<'_, SessionStore>,
+) -> Result<FileUploadResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let path = PathBuf::from(&file_path);
+
+    let (file_size, modified_at) = match file_fingerprint(&path).await {
+        Ok(fingerprint) => fingerprint,
+        Err(e) => {
+            return Ok(FileUploadResult {
+                success: false,
+                upload_id: None,
+                url: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    if file_size == 0 || file_size > MAX_FILE_SIZE {
+        return Ok(FileUploadResult {
+            success: false,
+            upload_id: None,
+            url: None,
+            error: Some("File must be between 1 byte and 2GB".to_string()),
+        });
+    }
+
+    let upload_id = uuid::Uuid::new_v4().to_string();
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(sanitize_file_name)
+        .unwrap_or_else(|| "file".to_string());
+    let key = format!("attachments/{}/{}/{}", user_id, upload_id, file_name);
+    let content_type = content_type_for(&path).to_string();
+
+    let s3_client = create_s3_client().await;
+    let created = s3_client
+        .create_multipart_upload()
+        .bucket(s3_bucket())
+        .key(&key)
+        .content_type(&content_type)
+        .send()
+        .await;
+
+    let s3_upload_id = match created.ok().and_then(|o| o.upload_id().map(|id| id.to_string())) {
+        Some(id) => id,
+        None => {
+            return Ok(FileUploadResult {
+                success: false,
+                upload_id: None,
+                url: None,
+                error: Some("Failed to start upload".to_string()),
+            });
+        }
+    };
+
+    let state = UploadState {
+        upload_id: upload_id.clone(),
+        user_id,
+        file_path,
+        file_size,
+        modified_at,
+        key,
+        s3_upload_id,
+        content_type,
+        completed_parts: vec![],
+    };
+    // Without saved state the upload could never be resumed or cancelled, so abort it
+    // rather than leave its parts billed in S3
+    if let Err(e) = save_state(&app, &state).await {
+        let _ = s3_client
+            .abort_multipart_upload()
+            .bucket(s3_bucket())
+            .key(&state.key)
+            .upload_id(&state.s3_upload_id)
+            .send()
+            .await;
+        return Err(e);
+    }
+
+    let result = run_upload(&app, state).await;
+    Ok(upload_result(upload_id, result))
+}
+
+/// Resume an upload that was interrupted by a crash or network loss
+#[command]
+pub async fn resume_file_upload(
+    upload_id: String,
+    app: AppHandle,
+    session_store: State<'_, SessionStore>,
+) -> Result<FileUploadResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+
+    let state = match load_state(&app, &upload_id).await {
+        Ok(state) if state.user_id == user_id => state,
+        Ok(_) | Err(_) => {
+            return Ok(FileUploadResult {
+                success: false,
+                upload_id: None,
+                url: None,
+                error: Some("Upload not found".to_string()),
+            });
+        }
+    };
+
+    // Parts already in S3 came from the old contents, so a changed file can't be resumed
+    let unchanged = file_fingerprint(Path::new(&state.file_path))
+        .await
+        .map(|fingerprint| fingerprint == (state.file_size, state.modified_at))
+        .unwrap_or(false);
+
+    if !unchanged {
+        return Ok(FileUploadResult {
+            success: false,
+            upload_id: Some(upload_id),
+            url: None,
+            error: Some("File was changed or removed since the upload started".to_string()),
+        });
+    }
+
+    let result = run_upload(&app, state).await;
+    Ok(upload_result(upload_id, result))
+}
+
+/// List the current user's unfinished uploads
+#[command]
+pub async fn get_pending_uploads(
+    app: AppHandle,
+    session_store: State<'_, SessionStore>,
+) -> Result<Vec<PendingUpload>, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+    let dir = uploads_dir(&app)?;
+
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let mut pending = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let state: UploadState = match tokio::fs::read(&path)
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+        {
+            Some(state) => state,
+            None => continue,
+        };
+
+        if state.user_id != user_id {
+            continue;
+        }
+
+        pending.push(PendingUpload {
+            file_name: Path::new(&state.file_path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            bytes_uploaded: bytes_uploaded(&state),
+            total_bytes: state.file_size,
+            upload_id: state.upload_id,
+        });
+    }
+
+    Ok(pending)
+}
+
+/// Cancel an unfinished upload and discard its uploaded parts
+#[command]
+pub async fn cancel_file_upload(
+    upload_id: String,
+    app: AppHandle,
+    session_store: State<'_, SessionStore>,
+) -> Result<FileUploadResult, String> {
+    let user_id = get_user_id_from_store(&session_store).await?;
+
+    let state = match load_state(&app, &upload_id).await {
+        Ok(state) if state.user_id == user_id => state,
+        Ok(_) | Err(_) => {
+            return Ok(FileUploadResult {
+                success: false,
+                upload_id: None,
+                url: None,
+                error: Some("Upload not found".to_string()),
+            });
+        }
+    };
+
+    let s3_client = create_s3_client().await;
+    let _ = s3_client
+        .abort_multipart_upload()
+        .bucket(s3_bucket())
+        .key(&state.key)
+        .upload_id(&state.s3_upload_id)
+        .send()
+        .await;
+
+    remove_state(&app, &upload_id).await;
+
+    Ok(FileUploadResult {
+        success: true,
+        upload_id: Some(upload_id),
+        url: None,
+        error: None,
+    })
+}
@@ -27,6 +27,12 @@ interface PublicSessionInfo {
   is_authenticated: boolean
 }
 
+// Routes emitted by the Rust deep-link router (src-tauri/src/deep_links.rs).
+// OAuth callbacks are handled in Rust and reported as `oauth-sign-in` instead.
+type DeepLink =
+  | { route: 'conversation'; conversation_id: string }
+  | { route: 'user_profile'; user_id: string }
+
 function useSystemTheme() {
   const [theme, setTheme] = useState<'light' | 'dark'>(() => {
     if (typeof window !== 'undefined') {
@@ -78,23 +84,32 @@ export default function App() {
   useEffect(() => {
     const initialize = async () => {
       try {
-        // Set up deep link listener for OAuth callback (Tauri only)
+        // Deep links are parsed and validated by the Rust backend (Tauri only)
         if (isTauri()) {
           try {
             const { listen } = await import('@tauri-apps/api/event')
-            await listen('deep-link', async (event) => {
+            await listen<DeepLink>('deep-link', (event) => {
               console.log('Deep link event received:', event.payload)
-              const url = event.payload as string
-              await handleOAuthCallback(url)
             })
-
-            const { onOpenUrl } = await import('@tauri-apps/plugin-deep-link')
-            await onOpenUrl(async (urls) => {
-              console.log('Deep link received:', urls)
-              for (const url of urls) {
-                await handleOAuthCallback(url)
+            await listen<{ success: boolean; error?: string }>('oauth-sign-in', async (event) => {
+              if (event.payload.success) {
+                await refreshSession()
+              } else {
+                console.error('OAuth sign-in failed:', event.payload.error)
               }
             })
+            await listen<{ url: string; reason: string }>('deep-link-rejected', (event) => {
+              console.warn('Deep link rejected:', event.payload.reason)
+            })
+
+            // Emitted by the backend when the refresh token is rejected
+            await listen('session-expired', () => {
+              setSession(null)
+              setHasProfile(false)
+            })
+
+            // Deliver links that arrived before these listeners existed (e.g. cold start)
+            await invoke('flush_pending_deep_links')
           } catch (err) {
             console.log('Deep link setup skipped:', err)
           }
@@ -117,26 +132,18 @@ export default function App() {
       setLoading(false)
     }
 
-    // Handle OAuth callback URL — delegate token exchange to Rust backend
-    const handleOAuthCallback = async (url: string) => {
-      if (url.includes('auth/callback') || url.includes('code=')) {
-        try {
-          // Pass the full callback URL to the Rust backend.
-          // The backend extracts the authorization code and exchanges it
-          // with Cognito for tokens.
-          await invoke('handle_oauth_callback', { callbackUrl: url })
-
-          // Refresh session state after successful OAuth
-          const currentSession = await invoke<PublicSessionInfo | null>('get_session')
-          setSession(currentSession)
-
-          if (currentSession?.user_id) {
-            const profileExists = await invoke<boolean>('check_profile_exists')
-            setHasProfile(profileExists)
-          }
-        } catch (err) {
-          console.error('OAuth callback failed:', err)
+    // The backend finished an OAuth sign-in and holds the new session
+    const refreshSession = async () => {
+      try {
+        const currentSession = await invoke<PublicSessionInfo | null>('get_session')
+        setSession(currentSession)
+
+        if (currentSession?.user_id) {
+          const profileExists = await invoke<boolean>('check_profile_exists')
+          setHasProfile(profileExists)
         }
+      } catch (err) {
+        console.error('Failed to load session after OAuth sign-in:', err)
       }
     }
 
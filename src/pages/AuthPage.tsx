@@ -5,11 +5,16 @@ import { Card, Flex, Text, TextField, Button, Heading, Box } from '@radix-ui/the
 import { FlickeringGrid } from "../components/ui/flickering-grid"
 import '../styles/AuthPage.css'
 
+type AuthChallenge =
+  | { type: 'totp' }
+  | { type: 'sms'; destination?: string }
+
 interface AuthResult {
   success: boolean
   error?: string
   user_id?: string
   needs_confirmation: boolean
+  challenge?: AuthChallenge
 }
 
 export default function AuthPage() {
@@ -17,6 +22,10 @@ export default function AuthPage() {
   const [password, setPassword] = useState('')
   const [loading, setLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
+  // Second factor the paused sign-in is waiting for
+  const [challenge, setChallenge] = useState<AuthChallenge | null>(null)
+  const [mfaCode, setMfaCode] = useState('')
+  const [useRecoveryCode, setUseRecoveryCode] = useState(false)
 
   const navigate = useNavigate()
 
@@ -50,9 +59,14 @@ export default function AuthPage() {
 
       if (result.success) {
         window.location.href = '/'
-      } else if (result.needs_confirmation) {
-        setError('Please confirm your email first. Check your inbox for a verification code.')
+      } else if (result.challenge) {
+        setChallenge(result.challenge)
+        setMfaCode('')
+        setUseRecoveryCode(false)
         setLoading(false)
+      } else if (result.needs_confirmation) {
+        // Confirming signs the user in, since the backend still holds this password
+        navigate('/signup', { state: { verifyEmail: email.trim() } })
       } else {
         setError(result.error || 'Sign in failed')
         setLoading(false)
@@ -63,9 +77,53 @@ export default function AuthPage() {
     }
   }
 
+  // Opens the hosted sign-in page in the browser; App picks up the result from `oauth-sign-in`
+  const signInWithGoogle = async () => {
+    setError(null)
+
+    try {
+      const result = await invoke<AuthResult>('start_oauth_sign_in', { provider: 'Google' })
+      if (!result.success) {
+        setError(result.error || 'Google sign-in failed')
+      }
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
+  const submitMfaCode = async () => {
+    if (!mfaCode.trim()) {
+      setError(useRecoveryCode ? 'Recovery code is required' : 'Verification code is required')
+      return
+    }
+
+    setLoading(true)
+    setError(null)
+
+    try {
+      const result = useRecoveryCode
+        ? await invoke<AuthResult>('sign_in_with_recovery_code', { recoveryCode: mfaCode.trim() })
+        : await invoke<AuthResult>('respond_to_mfa_challenge', { code: mfaCode.trim() })
+
+      if (result.success) {
+        window.location.href = '/'
+      } else {
+        setError(result.error || 'Verification failed')
+        setLoading(false)
+      }
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+      setLoading(false)
+    }
+  }
+
   const handleKeyDown = (e: React.KeyboardEvent) => {
     if (e.key === 'Enter' && !loading) {
-      signInWithEmail()
+      if (challenge) {
+        submitMfaCode()
+      } else {
+        signInWithEmail()
+      }
     }
   }
 
@@ -117,6 +175,64 @@ export default function AuthPage() {
       {/* Login card */}
       <Box maxWidth="400px" width="100%" className="relative z-10">
         <Card size="4" variant="surface">
+          {challenge ? (
+          <Flex direction="column" gap="4">
+            <Heading align="center" size="6">Two-Factor Authentication</Heading>
+
+            <Text size="2" align="center" color="gray">
+              {useRecoveryCode
+                ? 'Enter one of your recovery codes. Two-factor authentication will be turned off so you can set it up again.'
+                : challenge.type === 'sms'
+                  ? `Enter the code we texted to ${challenge.destination ?? 'your phone'}`
+                  : 'Enter the code from your authenticator app'}
+            </Text>
+
+            <TextField.Root
+              id="mfaCode"
+              type="text"
+              placeholder={useRecoveryCode ? 'XXXXX-XXXXX' : 'Enter 6-digit code'}
+              value={mfaCode}
+              onChange={(e) => setMfaCode(e.target.value)}
+              onKeyDown={handleKeyDown}
+              size="3"
+            />
+
+            <Flex direction="column" gap="2">
+              <Button size="3" onClick={submitMfaCode} disabled={loading}>
+                {loading ? 'Verifying...' : 'Verify'}
+              </Button>
+
+              <Button
+                size="3"
+                variant="soft"
+                onClick={() => {
+                  setUseRecoveryCode(!useRecoveryCode)
+                  setMfaCode('')
+                  setError(null)
+                }}
+              >
+                {useRecoveryCode ? 'Use a verification code' : 'Use a recovery code'}
+              </Button>
+
+              <Button
+                size="3"
+                variant="ghost"
+                onClick={() => {
+                  setChallenge(null)
+                  setError(null)
+                }}
+              >
+                Back
+              </Button>
+            </Flex>
+
+            {error && (
+              <Text color="red" size="2" align="center">
+                {error}
+              </Text>
+            )}
+          </Flex>
+          ) : (
           <Flex direction="column" gap="4">
             <Heading align="center" size="6">Cryptext</Heading>
 
@@ -159,6 +275,15 @@ export default function AuthPage() {
                 {loading ? 'Signing in...' : 'Sign In'}
               </Button>
 
+              <Button
+                size="3"
+                variant="outline"
+                onClick={signInWithGoogle}
+                disabled={loading}
+              >
+                Sign in with Google
+              </Button>
+
               <Button 
                 size="3" 
                 variant="soft"
@@ -174,6 +299,7 @@ export default function AuthPage() {
               </Text>
             )}
           </Flex>
+          )}
         </Card>
       </Box>
     </div>
@@ -173,15 +173,10 @@ export default function ProfilePage() {
         reader.readAsDataURL(selectedFile)
       })
 
-      const extension = selectedFile.name.split('.').pop() || 'png'
-      const contentType = selectedFile.type
-
       const result = await invoke<{ success: boolean; url?: string; error?: string }>(
         'upload_profile_image',
         {
           imageData: base64,
-          fileName: `avatar.${extension}`,
-          contentType,
         }
       )
 
@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react'
-import { useNavigate } from 'react-router-dom'
+import { useNavigate, useLocation } from 'react-router-dom'
 import { invoke } from '@tauri-apps/api/core'
 import { Card, Flex, Text, TextField, Button, Heading, Box } from '@radix-ui/themes'
 import { FlickeringGrid } from "../components/ui/flickering-grid"
@@ -12,16 +12,29 @@ interface AuthResult {
   needs_confirmation: boolean
 }
 
+// Seconds between "resend code" requests; the backend enforces the same limit
+const RESEND_COOLDOWN_SECONDS = 60
+
 export default function SignupPage() {
   const navigate = useNavigate()
-  const [email, setEmail] = useState('')
+  // Set when the login page sends an unconfirmed account here to enter its code
+  const verifyEmail = (useLocation().state as { verifyEmail?: string } | null)?.verifyEmail
+  const [email, setEmail] = useState(verifyEmail ?? '')
   const [password, setPassword] = useState('')
   const [confirmPassword, setConfirmPassword] = useState('')
   const [phone, setPhone] = useState('')
   const [verificationCode, setVerificationCode] = useState('')
   const [error, setError] = useState<string | null>(null)
   const [loading, setLoading] = useState(false)
-  const [step, setStep] = useState<'signup' | 'verify'>('signup')
+  const [step, setStep] = useState<'signup' | 'verify'>(verifyEmail ? 'verify' : 'signup')
+  const [notice, setNotice] = useState<string | null>(null)
+  const [resendCooldown, setResendCooldown] = useState(0)
+
+  useEffect(() => {
+    if (resendCooldown <= 0) return
+    const timer = setTimeout(() => setResendCooldown(resendCooldown - 1), 1000)
+    return () => clearTimeout(timer)
+  }, [resendCooldown])
 
   // Track window size for responsive grid
   const [windowSize, setWindowSize] = useState({
@@ -72,6 +85,7 @@ export default function SignupPage() {
         if (result.needs_confirmation) {
           // Cognito sent a verification code to the email
           setStep('verify')
+          setResendCooldown(RESEND_COOLDOWN_SECONDS)
           setLoading(false)
         } else {
           // Auto-confirmed, go to profile setup
@@ -104,16 +118,10 @@ export default function SignupPage() {
       })
 
       if (result.success) {
-        // Now sign them in automatically
-        const signInResult = await invoke<AuthResult>('sign_in', {
-          email: email.trim(),
-          password,
-        })
-
-        if (signInResult.success) {
+        // The backend signs the user in when it still has their password
+        if (result.user_id) {
           navigate('/profile')
         } else {
-          // Confirmed but sign-in failed, send to login page
           navigate('/')
         }
       } else {
@@ -126,6 +134,26 @@ export default function SignupPage() {
     }
   }
 
+  const handleResend = async () => {
+    setError(null)
+    setNotice(null)
+
+    try {
+      const result = await invoke<AuthResult>('resend_confirmation_code', {
+        email: email.trim(),
+      })
+
+      if (result.success) {
+        setNotice('A new code is on its way')
+        setResendCooldown(RESEND_COOLDOWN_SECONDS)
+      } else {
+        setError(result.error || 'Failed to resend code')
+      }
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
   const handleKeyDown = (e: React.KeyboardEvent) => {
     if (e.key === 'Enter' && !loading) {
       if (step === 'signup') {
@@ -302,18 +330,34 @@ export default function SignupPage() {
                   {loading ? 'Verifying...' : 'Verify & Sign In'}
                 </Button>
 
+                <Button
+                  size="3"
+                  variant="soft"
+                  onClick={handleResend}
+                  disabled={resendCooldown > 0}
+                >
+                  {resendCooldown > 0 ? `Resend code in ${resendCooldown}s` : 'Resend code'}
+                </Button>
+
                 <Button
                   size="3"
                   variant="soft"
                   onClick={() => {
                     setStep('signup')
                     setError(null)
+                    setNotice(null)
                   }}
                 >
                   Back
                 </Button>
               </Flex>
 
+              {notice && !error && (
+                <Text color="green" size="2" align="center">
+                  {notice}
+                </Text>
+              )}
+
               {error && (
                 <Text color="red" size="2" align="center">
                   {error}